@@ -0,0 +1,301 @@
+use std::fmt;
+
+use super::json_value::JSONValue;
+use super::object::JSONObject;
+use super::path::{Path, PathSegment};
+use super::util::signed_num_64::SignedNum64;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AccessErrorKind {
+    /// The object has no member with the requested key, or the array no element at the index.
+    Missing,
+    /// The value has a different type than requested.
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+/// Explains why a lookup through `Access` failed and where.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AccessError {
+    path: Path,
+    kind: AccessErrorKind,
+}
+
+impl AccessError {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn kind(&self) -> &AccessErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            AccessErrorKind::Missing => write!(f, "{}: missing", self.path),
+            AccessErrorKind::TypeMismatch { expected, found } => {
+                write!(f, "{}: expected {}, found {}", self.path, expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccessError {}
+
+/// A value together with its path from the document root, for lookups that fail with an
+/// `AccessError` instead of `None`.
+///
+/// ```
+/// use rust_json::json;
+///
+/// let value = json!({"users": [{"name": "Alice", "age": 15}]});
+/// let user = value.req("users").and_then(|users| users.idx(0)).unwrap();
+/// assert_eq!(Ok("Alice"), user.str("name"));
+/// assert_eq!(
+///     "$.users[0].age: expected string, found number",
+///     user.str("age").unwrap_err().to_string()
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Access<'a> {
+    value: &'a JSONValue,
+    path: Path,
+}
+
+impl<'a> Access<'a> {
+    pub fn new(value: &'a JSONValue) -> Self {
+        Self {
+            value,
+            path: Path::root(),
+        }
+    }
+
+    pub fn value(&self) -> &'a JSONValue {
+        self.value
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn mismatch(&self, expected: &'static str) -> AccessError {
+        AccessError {
+            path: self.path.clone(),
+            kind: AccessErrorKind::TypeMismatch {
+                expected,
+                found: match self.value {
+                    JSONValue::Utf16String(_) => "string with lone surrogates",
+                    value => value.type_name(),
+                },
+            },
+        }
+    }
+
+    fn child(&self, value: &'a JSONValue, segment: PathSegment) -> Access<'a> {
+        Access {
+            value,
+            path: self.path.join(segment),
+        }
+    }
+
+    /// Looks up a required object member.
+    pub fn req(&self, key: &str) -> Result<Access<'a>, AccessError> {
+        let obj = self.as_object()?;
+        match obj.get(key) {
+            Some(value) => Ok(self.child(value, PathSegment::Key(key.into()))),
+            None => Err(AccessError {
+                path: self.path.join(key),
+                kind: AccessErrorKind::Missing,
+            }),
+        }
+    }
+
+    /// Looks up an optional object member. Fails only if the value is not an object.
+    pub fn opt(&self, key: &str) -> Result<Option<Access<'a>>, AccessError> {
+        let obj = self.as_object()?;
+        Ok(obj
+            .get(key)
+            .map(|value| self.child(value, PathSegment::Key(key.into()))))
+    }
+
+    /// Looks up a required array element.
+    pub fn idx(&self, index: usize) -> Result<Access<'a>, AccessError> {
+        let arr = self.as_array()?;
+        match arr.get(index) {
+            Some(value) => Ok(self.child(value, PathSegment::Index(index))),
+            None => Err(AccessError {
+                path: self.path.join(index),
+                kind: AccessErrorKind::Missing,
+            }),
+        }
+    }
+
+    /// Iterates over the array elements, each with its own path.
+    pub fn elements(&self) -> Result<impl Iterator<Item = Access<'a>> + '_, AccessError> {
+        let arr = self.as_array()?;
+        Ok(arr
+            .iter()
+            .enumerate()
+            .map(|(i, value)| self.child(value, PathSegment::Index(i))))
+    }
+
+    /// Rejects strings holding lone surrogates, which have no `&str` form.
+    pub fn as_str(&self) -> Result<&'a str, AccessError> {
+        self.value.as_str().ok_or_else(|| self.mismatch("string"))
+    }
+
+    pub fn as_bool(&self) -> Result<bool, AccessError> {
+        self.value.as_bool().ok_or_else(|| self.mismatch("boolean"))
+    }
+
+    /// Accepts integers only; floats are rejected rather than truncated.
+    pub fn as_i64(&self) -> Result<i64, AccessError> {
+        match self.value {
+            JSONValue::Number(SignedNum64::Integer(num)) => Ok(*num),
+            _ => Err(self.mismatch("integer")),
+        }
+    }
+
+    pub fn as_f64(&self) -> Result<f64, AccessError> {
+        self.value.as_f64().ok_or_else(|| self.mismatch("number"))
+    }
+
+    pub fn as_array(&self) -> Result<&'a Vec<JSONValue>, AccessError> {
+        self.value.as_array().ok_or_else(|| self.mismatch("array"))
+    }
+
+    pub fn as_object(&self) -> Result<&'a JSONObject, AccessError> {
+        self.value
+            .as_object()
+            .ok_or_else(|| self.mismatch("object"))
+    }
+
+    /// Shorthand for `req(key)?.as_str()`.
+    pub fn str(&self, key: &str) -> Result<&'a str, AccessError> {
+        self.req(key)?.as_str()
+    }
+
+    /// Shorthand for `req(key)?.as_bool()`.
+    pub fn bool(&self, key: &str) -> Result<bool, AccessError> {
+        self.req(key)?.as_bool()
+    }
+
+    /// Shorthand for `req(key)?.as_i64()`.
+    pub fn i64(&self, key: &str) -> Result<i64, AccessError> {
+        self.req(key)?.as_i64()
+    }
+
+    /// Shorthand for `req(key)?.as_f64()`.
+    pub fn f64(&self, key: &str) -> Result<f64, AccessError> {
+        self.req(key)?.as_f64()
+    }
+
+    /// Shorthand for `req(key)?.as_array()`.
+    pub fn array(&self, key: &str) -> Result<&'a Vec<JSONValue>, AccessError> {
+        self.req(key)?.as_array()
+    }
+
+    /// Shorthand for `req(key)?.as_object()`.
+    pub fn object(&self, key: &str) -> Result<&'a JSONObject, AccessError> {
+        self.req(key)?.as_object()
+    }
+}
+
+impl JSONValue {
+    /// Starts a fallible lookup at this value; see `Access`.
+    pub fn access(&self) -> Access<'_> {
+        Access::new(self)
+    }
+
+    /// Shorthand for `access().req(key)`.
+    pub fn req(&self, key: &str) -> Result<Access<'_>, AccessError> {
+        self.access().req(key)
+    }
+
+    /// Shorthand for `access().idx(index)`.
+    pub fn idx(&self, index: usize) -> Result<Access<'_>, AccessError> {
+        self.access().idx(index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parser::Parser;
+    use super::super::utf16_string::Utf16String;
+    use super::*;
+
+    fn document() -> JSONValue {
+        Parser::parse("{\"users\": [{\"name\": \"Bob\", \"age\": 20, \"score\": 1.5}]}").unwrap()
+    }
+
+    #[test]
+    fn success() {
+        let value = document();
+        let user = value.req("users").and_then(|users| users.idx(0)).unwrap();
+
+        assert_eq!(Ok("Bob"), user.str("name"));
+        assert_eq!(Ok(20), user.i64("age"));
+        assert_eq!(Ok(1.5), user.f64("score"));
+        assert_eq!("$.users[0]", user.path().to_string());
+        assert!(user.opt("email").unwrap().is_none());
+    }
+
+    #[test]
+    fn errors() {
+        let value = document();
+        let users = value.req("users").unwrap();
+
+        assert_eq!(
+            "$.users[0].age: expected string, found number",
+            users
+                .idx(0)
+                .and_then(|u| u.str("age"))
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "$.users[0].score: expected integer, found number",
+            users
+                .idx(0)
+                .and_then(|u| u.i64("score"))
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!("$.users[3]: missing", users.idx(3).unwrap_err().to_string());
+        assert_eq!(
+            "$.users[0].email: missing",
+            users
+                .idx(0)
+                .and_then(|u| u.req("email"))
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            "$.users: expected object, found array",
+            users.req("name").unwrap_err().to_string()
+        );
+
+        let lone = JSONValue::Utf16String(Utf16String::from_units(vec![0xd800]));
+        assert_eq!(
+            "$: expected string, found string with lone surrogates",
+            lone.access().as_str().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn elements() {
+        let value = document();
+        let paths: Vec<String> = value
+            .req("users")
+            .unwrap()
+            .elements()
+            .unwrap()
+            .map(|user| user.path().to_string())
+            .collect();
+        assert_eq!(vec!["$.users[0]"], paths);
+    }
+}
@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::json_value::{JSONValue, Key};
+use super::object::JSONObject;
+use super::utf16_string::Utf16String;
+use super::util::signed_num_64::SignedNum64;
+
+/// Returned by `TryFrom<JSONValue>` when the value has a different type than requested.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConversionError {
+    expected: &'static str,
+    found: &'static str,
+}
+
+impl ConversionError {
+    pub fn new(expected: &'static str, found: &JSONValue) -> Self {
+        Self {
+            expected,
+            found: found.type_name(),
+        }
+    }
+
+    pub fn expected(&self) -> &'static str {
+        self.expected
+    }
+
+    pub fn found(&self) -> &'static str {
+        self.found
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl From<bool> for JSONValue {
+    fn from(value: bool) -> Self {
+        if value {
+            JSONValue::True
+        } else {
+            JSONValue::False
+        }
+    }
+}
+
+macro_rules! from_signed {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for JSONValue {
+                fn from(value: $t) -> Self {
+                    JSONValue::Number(SignedNum64::Integer(value as i64))
+                }
+            }
+        )*
+    };
+}
+
+from_signed!(i8, i16, i32, i64, isize, u8, u16, u32);
+
+/// Values beyond `i64::MAX` become floats.
+macro_rules! from_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for JSONValue {
+                fn from(value: $t) -> Self {
+                    match i64::try_from(value) {
+                        Ok(value) => JSONValue::Number(SignedNum64::Integer(value)),
+                        Err(_) => JSONValue::Number(SignedNum64::Float(value as f64)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_unsigned!(u64, usize);
+
+impl From<f32> for JSONValue {
+    fn from(value: f32) -> Self {
+        JSONValue::Number(SignedNum64::Float(value as f64))
+    }
+}
+
+impl From<f64> for JSONValue {
+    fn from(value: f64) -> Self {
+        JSONValue::Number(SignedNum64::Float(value))
+    }
+}
+
+impl From<SignedNum64> for JSONValue {
+    fn from(value: SignedNum64) -> Self {
+        JSONValue::Number(value)
+    }
+}
+
+impl From<&str> for JSONValue {
+    fn from(value: &str) -> Self {
+        JSONValue::String(value.to_string())
+    }
+}
+
+impl From<String> for JSONValue {
+    fn from(value: String) -> Self {
+        JSONValue::String(value)
+    }
+}
+
+impl From<Utf16String> for JSONValue {
+    fn from(value: Utf16String) -> Self {
+        JSONValue::Utf16String(value)
+    }
+}
+
+impl<T: Into<JSONValue>> From<Option<T>> for JSONValue {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => JSONValue::Null,
+        }
+    }
+}
+
+impl<T: Into<JSONValue>> From<Vec<T>> for JSONValue {
+    fn from(value: Vec<T>) -> Self {
+        JSONValue::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<JSONObject> for JSONValue {
+    fn from(value: JSONObject) -> Self {
+        JSONValue::Object(value)
+    }
+}
+
+impl<T: Into<JSONValue>> From<HashMap<String, T>> for JSONValue {
+    fn from(value: HashMap<String, T>) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+impl<T: Into<JSONValue>> FromIterator<T> for JSONValue {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        JSONValue::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<Key>, V: Into<JSONValue>> FromIterator<(K, V)> for JSONValue {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        JSONValue::Object(iter.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+impl TryFrom<JSONValue> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, Self::Error> {
+        value
+            .as_bool()
+            .ok_or_else(|| ConversionError::new("boolean", &value))
+    }
+}
+
+/// Only integers convert; floats are rejected rather than truncated.
+impl TryFrom<JSONValue> for i64 {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, Self::Error> {
+        match value {
+            JSONValue::Number(SignedNum64::Integer(num)) => Ok(num),
+            _ => Err(ConversionError::new("integer", &value)),
+        }
+    }
+}
+
+impl TryFrom<JSONValue> for u64 {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, Self::Error> {
+        match value {
+            JSONValue::Number(SignedNum64::Integer(num)) if num >= 0 => Ok(num as u64),
+            _ => Err(ConversionError::new("unsigned integer", &value)),
+        }
+    }
+}
+
+impl TryFrom<JSONValue> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, Self::Error> {
+        value
+            .as_f64()
+            .ok_or_else(|| ConversionError::new("number", &value))
+    }
+}
+
+impl TryFrom<JSONValue> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, Self::Error> {
+        match value {
+            JSONValue::String(s) => Ok(s),
+            JSONValue::Utf16String(s) => Ok(s.to_string_lossy()),
+            _ => Err(ConversionError::new("string", &value)),
+        }
+    }
+}
+
+impl TryFrom<JSONValue> for Vec<JSONValue> {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, Self::Error> {
+        match value {
+            JSONValue::Array(arr) => Ok(arr),
+            _ => Err(ConversionError::new("array", &value)),
+        }
+    }
+}
+
+impl TryFrom<JSONValue> for JSONObject {
+    type Error = ConversionError;
+
+    fn try_from(value: JSONValue) -> Result<Self, Self::Error> {
+        match value {
+            JSONValue::Object(obj) => Ok(obj),
+            _ => Err(ConversionError::new("object", &value)),
+        }
+    }
+}
+
+macro_rules! eq_number {
+    ($($t:ty),*) => {
+        $(
+            impl PartialEq<$t> for JSONValue {
+                fn eq(&self, other: &$t) -> bool {
+                    match self {
+                        JSONValue::Number(SignedNum64::Integer(num)) => {
+                            i64::try_from(*other).is_ok_and(|other| *num == other)
+                        }
+                        JSONValue::Number(SignedNum64::Float(num)) => *num == *other as f64,
+                        _ => false,
+                    }
+                }
+            }
+
+            impl PartialEq<JSONValue> for $t {
+                fn eq(&self, other: &JSONValue) -> bool {
+                    other == self
+                }
+            }
+        )*
+    };
+}
+
+eq_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! eq_float {
+    ($($t:ty),*) => {
+        $(
+            impl PartialEq<$t> for JSONValue {
+                fn eq(&self, other: &$t) -> bool {
+                    self.as_f64() == Some(*other as f64)
+                }
+            }
+
+            impl PartialEq<JSONValue> for $t {
+                fn eq(&self, other: &JSONValue) -> bool {
+                    other == self
+                }
+            }
+        )*
+    };
+}
+
+eq_float!(f32, f64);
+
+impl PartialEq<bool> for JSONValue {
+    fn eq(&self, other: &bool) -> bool {
+        self.as_bool() == Some(*other)
+    }
+}
+
+impl PartialEq<JSONValue> for bool {
+    fn eq(&self, other: &JSONValue) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<str> for JSONValue {
+    fn eq(&self, other: &str) -> bool {
+        match self {
+            JSONValue::String(s) => s == other,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<&str> for JSONValue {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<String> for JSONValue {
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<JSONValue> for str {
+    fn eq(&self, other: &JSONValue) -> bool {
+        other == self
+    }
+}
+
+impl PartialEq<JSONValue> for &str {
+    fn eq(&self, other: &JSONValue) -> bool {
+        other == *self
+    }
+}
+
+impl PartialEq<JSONValue> for String {
+    fn eq(&self, other: &JSONValue) -> bool {
+        other == self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_primitives() {
+        assert_eq!(JSONValue::True, JSONValue::from(true));
+        assert_eq!(
+            JSONValue::Number(SignedNum64::Integer(-3)),
+            JSONValue::from(-3)
+        );
+        assert_eq!(
+            JSONValue::Number(SignedNum64::Float(u64::MAX as f64)),
+            JSONValue::from(u64::MAX)
+        );
+        assert_eq!(
+            JSONValue::Number(SignedNum64::Float(0.5)),
+            JSONValue::from(0.5)
+        );
+        assert_eq!(JSONValue::String("a".to_string()), JSONValue::from("a"));
+        assert_eq!(JSONValue::Null, JSONValue::from(None::<i64>));
+    }
+
+    #[test]
+    fn from_collections() {
+        assert_eq!(
+            JSONValue::Array(vec![JSONValue::from(1), JSONValue::from(2)]),
+            JSONValue::from(vec![1, 2])
+        );
+
+        let mut map = HashMap::new();
+        map.insert("a".to_string(), 1);
+        let mut object = JSONObject::new();
+        object.insert("a", JSONValue::from(1));
+        assert_eq!(JSONValue::Object(object.clone()), JSONValue::from(map));
+
+        assert_eq!(
+            JSONValue::Object(object),
+            [("a", 1)].into_iter().collect::<JSONValue>()
+        );
+        assert_eq!(
+            JSONValue::from(vec!["x", "y"]),
+            ["x", "y"].into_iter().collect::<JSONValue>()
+        );
+    }
+
+    #[test]
+    fn try_from() {
+        assert_eq!(Ok(3), i64::try_from(JSONValue::from(3)));
+        assert_eq!(Ok(true), bool::try_from(JSONValue::True));
+        assert_eq!(Ok("a".to_string()), String::try_from(JSONValue::from("a")));
+        assert_eq!(
+            Err(ConversionError {
+                expected: "integer",
+                found: "string"
+            }),
+            i64::try_from(JSONValue::from("3"))
+        );
+        assert_eq!(
+            "expected unsigned integer, found number",
+            u64::try_from(JSONValue::from(-1)).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn partial_eq() {
+        assert_eq!(JSONValue::from(20), 20);
+        assert_eq!(JSONValue::from(20.0), 20);
+        assert_eq!(JSONValue::from("Bob"), "Bob");
+        assert_eq!(JSONValue::True, true);
+        assert_ne!(JSONValue::from("20"), 20);
+        assert_eq!(1.5, JSONValue::from(1.5));
+    }
+}
@@ -0,0 +1,377 @@
+use std::fmt;
+
+use super::json_value::JSONValue;
+use super::path::{Path, PathSegment};
+use super::serializer::Serializer;
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DiffOptions {
+    /// Compares arrays by membership instead of position, so that reordering is not a change.
+    /// Removed elements are reported at their index in the old array and added elements at their
+    /// index in the new one.
+    pub arrays_as_sets: bool,
+}
+
+/// A single difference between two values, located by its path.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Change<'a> {
+    Added {
+        path: Path,
+        value: &'a JSONValue,
+    },
+    Removed {
+        path: Path,
+        value: &'a JSONValue,
+    },
+    Changed {
+        path: Path,
+        old: &'a JSONValue,
+        new: &'a JSONValue,
+    },
+}
+
+impl Change<'_> {
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Writes e.g. `+ $.a: 1`, `- $.b: "x"` or `~ $.c: 1 -> 2`.
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => {
+                write!(f, "+ {}: {}", path, Serializer::serialize(value))
+            }
+            Change::Removed { path, value } => {
+                write!(f, "- {}: {}", path, Serializer::serialize(value))
+            }
+            Change::Changed { path, old, new } => write!(
+                f,
+                "~ {}: {} -> {}",
+                path,
+                Serializer::serialize(old),
+                Serializer::serialize(new)
+            ),
+        }
+    }
+}
+
+/// The differences between two values, created by `JSONValue::diff`.
+///
+/// Objects are compared as maps, ignoring member order. Displays as an uncolored tree; see
+/// `render`.
+#[derive(Debug, Clone)]
+pub struct Diff<'a> {
+    old: &'a JSONValue,
+    new: &'a JSONValue,
+    options: DiffOptions,
+    changes: Vec<Change<'a>>,
+}
+
+impl<'a> Diff<'a> {
+    pub fn new(old: &'a JSONValue, new: &'a JSONValue, options: DiffOptions) -> Self {
+        let mut changes = Vec::new();
+        collect(old, new, &mut Path::root(), &options, &mut changes);
+        Self {
+            old,
+            new,
+            options,
+            changes,
+        }
+    }
+
+    /// The changes in document order of the old value, followed by additions.
+    pub fn changes(&self) -> &[Change<'a>] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Renders the diff as a unified-style tree: containers holding changes are expanded, with
+    /// removed lines prefixed by `-` and added lines by `+`, and unchanged values are shown
+    /// compactly on a single line for context. With `color`, removed and added lines are
+    /// highlighted with ANSI escape codes.
+    ///
+    /// ```
+    /// use rust_json::json;
+    ///
+    /// let old = json!({"name": "api", "replicas": 2, "ports": [80]});
+    /// let new = json!({"name": "api", "replicas": 3, "ports": [80, 443]});
+    /// assert_eq!(
+    ///     concat!(
+    ///         "  {\n",
+    ///         "    \"name\": \"api\"\n",
+    ///         "-   \"replicas\": 2\n",
+    ///         "+   \"replicas\": 3\n",
+    ///         "    \"ports\": [\n",
+    ///         "      80\n",
+    ///         "+     443\n",
+    ///         "    ]\n",
+    ///         "  }\n",
+    ///     ),
+    ///     old.diff(&new).render(false)
+    /// );
+    /// ```
+    pub fn render(&self, color: bool) -> String {
+        let mut renderer = Renderer {
+            output: String::new(),
+            color,
+            arrays_as_sets: self.options.arrays_as_sets,
+        };
+        renderer.render(None, self.old, self.new, 0);
+        renderer.output
+    }
+}
+
+impl fmt::Display for Diff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render(false))
+    }
+}
+
+fn collect<'a>(
+    old: &'a JSONValue,
+    new: &'a JSONValue,
+    path: &mut Path,
+    options: &DiffOptions,
+    changes: &mut Vec<Change<'a>>,
+) {
+    match (old, new) {
+        (JSONValue::Object(old), JSONValue::Object(new)) => {
+            for (key, old_val) in old {
+                path.push(PathSegment::Key(key.clone()));
+                match new.get(key) {
+                    Some(new_val) => collect(old_val, new_val, path, options, changes),
+                    None => changes.push(Change::Removed {
+                        path: path.clone(),
+                        value: old_val,
+                    }),
+                }
+                path.pop();
+            }
+            for (key, new_val) in new.iter().filter(|(key, _)| !old.contains_key(key)) {
+                changes.push(Change::Added {
+                    path: path.join(PathSegment::Key(key.clone())),
+                    value: new_val,
+                });
+            }
+        }
+        (JSONValue::Array(old), JSONValue::Array(new)) if options.arrays_as_sets => {
+            for (i, old_val) in old.iter().enumerate() {
+                if !new.contains(old_val) {
+                    changes.push(Change::Removed {
+                        path: path.join(i),
+                        value: old_val,
+                    });
+                }
+            }
+            for (i, new_val) in new.iter().enumerate() {
+                if !old.contains(new_val) {
+                    changes.push(Change::Added {
+                        path: path.join(i),
+                        value: new_val,
+                    });
+                }
+            }
+        }
+        (JSONValue::Array(old), JSONValue::Array(new)) => {
+            for (i, old_val) in old.iter().enumerate() {
+                match new.get(i) {
+                    Some(new_val) => {
+                        path.push(i);
+                        collect(old_val, new_val, path, options, changes);
+                        path.pop();
+                    }
+                    None => changes.push(Change::Removed {
+                        path: path.join(i),
+                        value: old_val,
+                    }),
+                }
+            }
+            for (i, new_val) in new.iter().enumerate().skip(old.len()) {
+                changes.push(Change::Added {
+                    path: path.join(i),
+                    value: new_val,
+                });
+            }
+        }
+        _ if old != new => changes.push(Change::Changed {
+            path: path.clone(),
+            old,
+            new,
+        }),
+        _ => {}
+    }
+}
+
+struct Renderer {
+    output: String,
+    color: bool,
+    arrays_as_sets: bool,
+}
+
+impl Renderer {
+    fn line(&mut self, sign: char, depth: usize, key: Option<&str>, text: &str) {
+        let color = match sign {
+            '-' => Some("\x1b[31m"),
+            '+' => Some("\x1b[32m"),
+            _ => None,
+        }
+        .filter(|_| self.color);
+
+        if let Some(code) = color {
+            self.output.push_str(code);
+        }
+        self.output.push(sign);
+        self.output.push(' ');
+        self.output.push_str(&"  ".repeat(depth));
+        if let Some(key) = key {
+            self.output
+                .push_str(&Serializer::serialize(&JSONValue::from(key)));
+            self.output.push_str(": ");
+        }
+        self.output.push_str(text);
+        if color.is_some() {
+            self.output.push_str("\x1b[0m");
+        }
+        self.output.push('\n');
+    }
+
+    fn value(&mut self, sign: char, depth: usize, key: Option<&str>, value: &JSONValue) {
+        self.line(sign, depth, key, &Serializer::serialize(value));
+    }
+
+    fn render(&mut self, key: Option<&str>, old: &JSONValue, new: &JSONValue, depth: usize) {
+        if old == new {
+            return self.value(' ', depth, key, old);
+        }
+
+        match (old, new) {
+            (JSONValue::Object(old), JSONValue::Object(new)) => {
+                self.line(' ', depth, key, "{");
+                for (k, old_val) in old {
+                    match new.get(k) {
+                        Some(new_val) => self.render(Some(k), old_val, new_val, depth + 1),
+                        None => self.value('-', depth + 1, Some(k), old_val),
+                    }
+                }
+                for (k, new_val) in new.iter().filter(|(k, _)| !old.contains_key(k)) {
+                    self.value('+', depth + 1, Some(k), new_val);
+                }
+                self.line(' ', depth, None, "}");
+            }
+            (JSONValue::Array(old), JSONValue::Array(new)) => {
+                self.line(' ', depth, key, "[");
+                if self.arrays_as_sets {
+                    for old_val in old {
+                        let sign = if new.contains(old_val) { ' ' } else { '-' };
+                        self.value(sign, depth + 1, None, old_val);
+                    }
+                    for new_val in new.iter().filter(|v| !old.contains(v)) {
+                        self.value('+', depth + 1, None, new_val);
+                    }
+                } else {
+                    for (i, old_val) in old.iter().enumerate() {
+                        match new.get(i) {
+                            Some(new_val) => self.render(None, old_val, new_val, depth + 1),
+                            None => self.value('-', depth + 1, None, old_val),
+                        }
+                    }
+                    for new_val in new.iter().skip(old.len()) {
+                        self.value('+', depth + 1, None, new_val);
+                    }
+                }
+                self.line(' ', depth, None, "]");
+            }
+            _ => {
+                self.value('-', depth, key, old);
+                self.value('+', depth, key, new);
+            }
+        }
+    }
+}
+
+impl JSONValue {
+    /// Compares this value with `other`; see `Diff`.
+    pub fn diff<'a>(&'a self, other: &'a JSONValue) -> Diff<'a> {
+        Diff::new(self, other, DiffOptions::default())
+    }
+
+    pub fn diff_with<'a>(&'a self, other: &'a JSONValue, options: DiffOptions) -> Diff<'a> {
+        Diff::new(self, other, options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    fn changes(diff: &Diff) -> Vec<String> {
+        diff.changes().iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn changes_list() {
+        let old = json!({"a": 1, "b": {"c": [1, 2, 3]}, "d": "x"});
+        let new = json!({"d": "x", "b": {"c": [1, 5]}, "e": null, "a": "1"});
+        assert_eq!(
+            vec![
+                "~ $.a: 1 -> \"1\"",
+                "~ $.b.c[1]: 2 -> 5",
+                "- $.b.c[2]: 3",
+                "+ $.e: null",
+            ],
+            changes(&old.diff(&new))
+        );
+        assert!(old.diff(&old.clone()).is_empty());
+        assert_eq!(
+            Some(&Path::root().join("a")),
+            old.diff(&new).changes().first().map(Change::path)
+        );
+    }
+
+    #[test]
+    fn arrays_as_sets() {
+        let old = json!({"tags": ["a", "b", "c"]});
+        let new = json!({"tags": ["c", "d", "a"]});
+        let options = DiffOptions {
+            arrays_as_sets: true,
+        };
+
+        let diff = old.diff_with(&new, options);
+        assert_eq!(
+            vec!["- $.tags[1]: \"b\"", "+ $.tags[1]: \"d\""],
+            changes(&diff)
+        );
+        assert_eq!(
+            "  {\n    \"tags\": [\n      \"a\"\n-     \"b\"\n      \"c\"\n+     \"d\"\n    ]\n  }\n",
+            diff.to_string()
+        );
+        assert!(json!([1, 2])
+            .diff_with(
+                &json!([2, 1]),
+                DiffOptions {
+                    arrays_as_sets: true
+                }
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn render_color() {
+        let old = json!({"a": [1]});
+        let new = json!({"a": {}});
+        assert_eq!(
+            "  {\n\x1b[31m-   \"a\": [1]\x1b[0m\n\x1b[32m+   \"a\": {}\x1b[0m\n  }\n",
+            old.diff(&new).render(true)
+        );
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::json_value::JSONValue;
+use super::object::JSONObject;
+use super::path::PathSegment;
+
+/// How array indices are written in flattened keys.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ArrayNotation {
+    /// `a[0].b`
+    #[default]
+    Brackets,
+    /// `a.0.b`. Object keys consisting only of digits are escaped to tell them apart.
+    Separator,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FlattenOptions {
+    /// Placed between the segments of a key; must not be empty or contain `escape`.
+    pub separator: String,
+    pub arrays: ArrayNotation,
+    /// Precedes characters of object keys that would otherwise be read as a separator or as
+    /// array notation.
+    pub escape: char,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            separator: ".".to_string(),
+            arrays: ArrayNotation::default(),
+            escape: '\\',
+        }
+    }
+}
+
+impl FlattenOptions {
+    fn brackets(&self) -> bool {
+        self.arrays == ArrayNotation::Brackets
+    }
+
+    fn push_key(&self, out: &mut String, key: &str) {
+        let digits = !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit());
+        for (i, c) in key.chars().enumerate() {
+            let special = c == self.escape
+                || self.separator.contains(c)
+                || (self.brackets() && (c == '[' || c == ']'))
+                || (!self.brackets() && digits && i == 0);
+            if special {
+                out.push(self.escape);
+            }
+            out.push(c);
+        }
+    }
+
+    fn flatten_into(
+        &self,
+        value: &JSONValue,
+        prefix: &mut String,
+        root: bool,
+        out: &mut JSONObject,
+    ) {
+        let len = prefix.len();
+        match value {
+            JSONValue::Object(obj) if !obj.is_empty() => {
+                for (key, val) in obj {
+                    if !root {
+                        prefix.push_str(&self.separator);
+                    }
+                    self.push_key(prefix, key);
+                    // An empty key would vanish before an index, so close it with a separator.
+                    if key.is_empty() && self.brackets() && is_nonempty_array(val) {
+                        prefix.push_str(&self.separator);
+                    }
+                    self.flatten_into(val, prefix, false, out);
+                    prefix.truncate(len);
+                }
+            }
+            JSONValue::Array(arr) if !arr.is_empty() => {
+                for (i, val) in arr.iter().enumerate() {
+                    if self.brackets() {
+                        prefix.push_str(&format!("[{}]", i));
+                    } else {
+                        if !root {
+                            prefix.push_str(&self.separator);
+                        }
+                        prefix.push_str(&i.to_string());
+                    }
+                    self.flatten_into(val, prefix, false, out);
+                    prefix.truncate(len);
+                }
+            }
+            _ => {
+                out.insert(prefix.as_str(), value.clone());
+            }
+        }
+    }
+
+    /// Splits a flattened key into path segments, or returns `None` if it is malformed.
+    fn parse_key(&self, key: &str) -> Option<Vec<PathSegment>> {
+        let mut segments = Vec::new();
+        let mut current = String::new();
+        let mut escaped = false;
+        // Whether a key segment is being read, as opposed to just having closed an index.
+        let mut open = true;
+        let mut rest = key;
+
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with(&self.separator) {
+                if open {
+                    segments.push(self.key_segment(&mut current, &mut escaped)?);
+                }
+                open = true;
+                rest = &rest[self.separator.len()..];
+            } else if self.brackets() && c == '[' {
+                if open && !current.is_empty() {
+                    segments.push(self.key_segment(&mut current, &mut escaped)?);
+                }
+                let end = rest.find(']')?;
+                let digits = &rest[1..end];
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                segments.push(PathSegment::Index(digits.parse().ok()?));
+                open = false;
+                rest = &rest[end + 1..];
+            } else if !open {
+                return None;
+            } else if c == self.escape {
+                let mut chars = rest[c.len_utf8()..].chars();
+                current.push(chars.next()?);
+                escaped = true;
+                rest = chars.as_str();
+            } else {
+                current.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        if open {
+            segments.push(self.key_segment(&mut current, &mut escaped)?);
+        }
+        Some(segments)
+    }
+
+    fn key_segment(&self, current: &mut String, escaped: &mut bool) -> Option<PathSegment> {
+        let text = std::mem::take(current);
+        let literal = std::mem::take(escaped);
+        if !self.brackets()
+            && !literal
+            && !text.is_empty()
+            && text.bytes().all(|b| b.is_ascii_digit())
+        {
+            return text.parse().ok().map(PathSegment::Index);
+        }
+        Some(PathSegment::Key(text.into()))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnflattenErrorKind {
+    /// The key is not valid in the configured notation, e.g. `a[x]`, or has an array index
+    /// beyond the elements placed so far.
+    InvalidKey,
+    /// The key needs a container where an earlier key already placed a value, or the other way
+    /// round, e.g. `a` and `a.b`.
+    Conflict,
+}
+
+/// Explains which flattened key could not be unflattened.
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnflattenError {
+    key: String,
+    kind: UnflattenErrorKind,
+}
+
+impl UnflattenError {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn kind(&self) -> &UnflattenErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for UnflattenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            UnflattenErrorKind::InvalidKey => write!(f, "invalid flattened key {:?}", self.key),
+            UnflattenErrorKind::Conflict => {
+                write!(f, "flattened key {:?} conflicts with another key", self.key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnflattenError {}
+
+fn is_nonempty_array(value: &JSONValue) -> bool {
+    matches!(value, JSONValue::Array(arr) if !arr.is_empty())
+}
+
+fn is_empty_container(value: &JSONValue) -> bool {
+    match value {
+        JSONValue::Object(obj) => obj.is_empty(),
+        JSONValue::Array(arr) => arr.is_empty(),
+        _ => false,
+    }
+}
+
+/// Stores `value` at `segments` below `root`, creating containers on the way. An array index may
+/// refer to an existing element or append one, so that a key cannot allocate an arbitrarily
+/// large array. `null` slots created along the way may be replaced, but not the explicit `null`
+/// leaves listed in `nulls`.
+fn place(
+    root: &mut JSONValue,
+    segments: Vec<PathSegment>,
+    value: JSONValue,
+    nulls: &mut HashSet<Vec<PathSegment>>,
+) -> Result<(), UnflattenErrorKind> {
+    let mut current = root;
+    for (depth, segment) in segments.iter().enumerate() {
+        if current.is_null() && nulls.contains(&segments[..depth]) {
+            return Err(UnflattenErrorKind::Conflict);
+        }
+        current = match segment {
+            PathSegment::Key(key) => {
+                if current.is_null() {
+                    *current = JSONValue::Object(JSONObject::new());
+                }
+                match current {
+                    JSONValue::Object(obj) => obj.entry(key.clone()).or_default(),
+                    _ => return Err(UnflattenErrorKind::Conflict),
+                }
+            }
+            PathSegment::Index(index) => {
+                let index = *index;
+                if current.is_null() {
+                    *current = JSONValue::Array(Vec::new());
+                }
+                match current {
+                    JSONValue::Array(arr) => {
+                        if index > arr.len() {
+                            return Err(UnflattenErrorKind::InvalidKey);
+                        }
+                        if index == arr.len() {
+                            arr.push(JSONValue::Null);
+                        }
+                        &mut arr[index]
+                    }
+                    _ => return Err(UnflattenErrorKind::Conflict),
+                }
+            }
+        };
+    }
+
+    if current.is_null() && !nulls.contains(&segments) {
+        if value.is_null() {
+            nulls.insert(segments);
+        }
+        *current = value;
+        Ok(())
+    } else if is_empty_container(&value) && current.type_name() == value.type_name() {
+        Ok(())
+    } else {
+        Err(UnflattenErrorKind::Conflict)
+    }
+}
+
+impl JSONValue {
+    /// Flattens the tree into a single-level object with the default `FlattenOptions`; see
+    /// `flatten_with`.
+    pub fn flatten(&self) -> JSONObject {
+        self.flatten_with(&FlattenOptions::default())
+    }
+
+    /// Flattens the tree into a single-level object mapping keys such as `a.b[0].c` to the
+    /// leaves, in document order. Empty objects and arrays are kept as leaves so that
+    /// `JSONObject::unflatten_with` restores them; a value that is not a container is stored
+    /// under the empty key. With bracket notation, an empty object key followed by an index is
+    /// closed with a separator, so `{"": [1]}` flattens to `.[0]` rather than `[0]`.
+    ///
+    /// ```
+    /// use rust_json::json;
+    ///
+    /// let value = json!({"a": {"b": [{"c": 1}], "d.e": true}});
+    /// let flat = value.flatten();
+    /// assert_eq!(json!({"a.b[0].c": 1, "a.d\\.e": true}).as_object(), Some(&flat));
+    /// assert_eq!(Ok(value), flat.unflatten());
+    /// ```
+    pub fn flatten_with(&self, options: &FlattenOptions) -> JSONObject {
+        let mut out = JSONObject::new();
+        if !is_empty_container(self) {
+            options.flatten_into(self, &mut String::new(), true, &mut out);
+        }
+        out
+    }
+}
+
+impl JSONObject {
+    /// Rebuilds a tree from keys written by `JSONValue::flatten`.
+    pub fn unflatten(self) -> Result<JSONValue, UnflattenError> {
+        self.unflatten_with(&FlattenOptions::default())
+    }
+
+    /// Rebuilds a tree from keys written by `JSONValue::flatten_with` with the same `options`.
+    /// Array indices must appear in order without gaps, as `flatten_with` writes them, and an
+    /// empty object unflattens to an empty object.
+    pub fn unflatten_with(self, options: &FlattenOptions) -> Result<JSONValue, UnflattenError> {
+        let mut root = JSONValue::Null;
+        let mut nulls = HashSet::new();
+        for (key, value) in self {
+            let error = |kind| UnflattenError {
+                key: key.to_string(),
+                kind,
+            };
+            let segments = options
+                .parse_key(&key)
+                .ok_or_else(|| error(UnflattenErrorKind::InvalidKey))?;
+            place(&mut root, segments, value, &mut nulls).map_err(error)?;
+        }
+        if root.is_null() {
+            root = JSONValue::Object(JSONObject::new());
+        }
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    fn keys(flat: &JSONObject) -> Vec<&str> {
+        flat.keys().map(|k| &**k).collect()
+    }
+
+    #[test]
+    fn flatten() {
+        let value = json!({"a": {"b": [1, {"c": null}], "e": {}}, "f[g]": "x", "": []});
+        let flat = value.flatten();
+        assert_eq!(
+            vec!["a.b[0]", "a.b[1].c", "a.e", "f\\[g\\]", ""],
+            keys(&flat)
+        );
+        assert_eq!(Ok(value), flat.unflatten());
+    }
+
+    #[test]
+    fn empty_keys() {
+        for value in [
+            json!({"": [1]}),
+            json!({"": {"a": [1]}}),
+            json!({"a": {"": [1]}}),
+            json!({"": {"": [[1]]}}),
+            json!([{"": [1]}]),
+        ] {
+            assert_eq!(
+                Ok(value.clone()),
+                value.flatten().unflatten(),
+                "{:?}",
+                value
+            );
+        }
+        assert_eq!(vec![".[0]"], keys(&json!({"": [1]}).flatten()));
+        assert_eq!(vec!["[0]"], keys(&json!([1]).flatten()));
+    }
+
+    #[test]
+    fn separator_notation() {
+        let options = FlattenOptions {
+            separator: "/".to_string(),
+            arrays: ArrayNotation::Separator,
+            escape: '~',
+        };
+        let value = json!([{"a/b": 1, "0": 2, "~": 3}]);
+        let flat = value.flatten_with(&options);
+        assert_eq!(vec!["0/a~/b", "0/~0", "0/~~"], keys(&flat));
+        assert_eq!(Ok(value), flat.unflatten_with(&options));
+    }
+
+    #[test]
+    fn unflatten() {
+        let flat = json!({"a[0].b": true, "a[1]": null, "a[0].c": 1, "a[2]": 1, "c": {}});
+        assert_eq!(
+            Ok(json!({"a": [{"b": true, "c": 1}, null, 1], "c": {}})),
+            flat.as_object().unwrap().clone().unflatten()
+        );
+        assert_eq!(Ok(json!({})), JSONObject::new().unflatten());
+
+        let error = json!({"a": 1, "a.b": 2})
+            .as_object()
+            .unwrap()
+            .clone()
+            .unflatten()
+            .unwrap_err();
+        assert_eq!(UnflattenErrorKind::Conflict, *error.kind());
+        assert_eq!("a.b", error.key());
+
+        for flat in [
+            json!({"a": null, "a.b": 1}),
+            json!({"a.b": 1, "a": null}),
+            json!({"a[0]": null, "a[0].b": 1}),
+            json!({"a": null, "\\a": 1}),
+        ] {
+            let error = flat.as_object().unwrap().clone().unflatten().unwrap_err();
+            assert_eq!(UnflattenErrorKind::Conflict, *error.kind(), "{:?}", flat);
+        }
+
+        for key in [
+            "a[x]",
+            "a[0]b",
+            "a\\",
+            "a[1",
+            "a[1]",
+            "a[99999999999999999]",
+        ] {
+            let error = JSONObject::from_iter([(key, JSONValue::Null)])
+                .unflatten()
+                .unwrap_err();
+            assert_eq!(UnflattenErrorKind::InvalidKey, *error.kind(), "{}", key);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::ops::{Index, IndexMut};
+
+use super::json_value::JSONValue;
+use super::object::JSONObject;
+
+static NULL: JSONValue = JSONValue::Null;
+
+/// Returns the array element, or `Null` if the value is not an array or the index is out of
+/// bounds.
+impl Index<usize> for JSONValue {
+    type Output = JSONValue;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get_as_array(index).unwrap_or(&NULL)
+    }
+}
+
+/// Returns the array element, turning `Null` into an empty array and appending a `Null`
+/// element first if `index` is the length of the array.
+///
+/// Panics if the value is neither an array nor `Null`, or if `index` is beyond the length.
+impl IndexMut<usize> for JSONValue {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        if self.is_null() {
+            *self = JSONValue::Array(Vec::new());
+        }
+
+        match self {
+            JSONValue::Array(arr) => {
+                let len = arr.len();
+                if index > len {
+                    panic!(
+                        "index out of bounds: the len is {} but the index is {}",
+                        len, index
+                    );
+                }
+                if index == len {
+                    arr.push(JSONValue::Null);
+                }
+                &mut arr[index]
+            }
+            _ => panic!("cannot index into {} with usize", self.type_name()),
+        }
+    }
+}
+
+/// Returns the value of the object member, or `Null` if the value is not an object or the key
+/// is missing.
+impl Index<&str> for JSONValue {
+    type Output = JSONValue;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get_as_object(key).unwrap_or(&NULL)
+    }
+}
+
+/// Returns the value of the object member, turning `Null` into an empty object and inserting
+/// the key with a `Null` value first if it is missing.
+///
+/// Panics if the value is neither an object nor `Null`.
+impl IndexMut<&str> for JSONValue {
+    fn index_mut(&mut self, key: &str) -> &mut Self::Output {
+        if self.is_null() {
+            *self = JSONValue::Object(JSONObject::new());
+        }
+
+        match self {
+            JSONValue::Object(obj) => {
+                if !obj.contains_key(key) {
+                    obj.insert(key, JSONValue::Null);
+                }
+                obj.get_mut(key).unwrap()
+            }
+            _ => panic!("cannot index into {} with str", self.type_name()),
+        }
+    }
+}
+
+impl Index<&String> for JSONValue {
+    type Output = JSONValue;
+
+    fn index(&self, key: &String) -> &Self::Output {
+        &self[key.as_str()]
+    }
+}
+
+impl IndexMut<&String> for JSONValue {
+    fn index_mut(&mut self, key: &String) -> &mut Self::Output {
+        &mut self[key.as_str()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parser::Parser;
+    use super::*;
+
+    #[test]
+    fn index() {
+        let value = Parser::parse("{\"users\": [{\"name\": \"Bob\", \"age\": 20}]}").unwrap();
+
+        assert_eq!(value["users"][0]["name"], "Bob");
+        assert_eq!(value["users"][0]["age"], 20);
+        assert_eq!(value["users"][1]["age"], JSONValue::Null);
+        assert_eq!(value["missing"]["a"][3], JSONValue::Null);
+        assert_eq!(value["users"]["name"], JSONValue::Null);
+    }
+
+    #[test]
+    fn index_mut_creates_containers() {
+        let mut value = JSONValue::Null;
+        value["a"]["b"] = 3.into();
+        value["list"][0] = 1.into();
+        value["list"][1] = "x".into();
+        value["a"]["c"] = true.into();
+
+        assert_eq!(
+            Parser::parse("{\"a\": {\"b\": 3, \"c\": true}, \"list\": [1, \"x\"]}"),
+            Ok(value)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds: the len is 1 but the index is 2")]
+    fn index_mut_beyond_end() {
+        let mut value = JSONValue::Array(vec![JSONValue::Null]);
+        value[2] = 1.into();
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn index_mut_huge_index() {
+        let mut value = JSONValue::Null;
+        value[usize::MAX] = 1.into();
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_mut_into_scalar() {
+        let mut value = JSONValue::from(1);
+        value["a"] = JSONValue::Null;
+    }
+}
@@ -0,0 +1,32 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Hands out a single shared allocation for each distinct string it is asked for.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(s) {
+            return interned.clone();
+        }
+
+        let interned: Arc<str> = Arc::from(s);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
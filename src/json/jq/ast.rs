@@ -0,0 +1,78 @@
+use super::super::json_value::JSONValue;
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) enum Expr {
+    Identity,
+    /// `..`: the input and all values nested in it, in document order.
+    Recurse,
+    Literal(JSONValue),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    /// `.[]`: the elements of an array or the values of an object.
+    Iterate(Box<Expr>),
+    /// `expr?`: suppresses errors.
+    Try(Box<Expr>),
+    /// `[expr]`, or `[]` for `None`.
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Neg(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    /// `a // b`: the truthy outputs of `a`, or the outputs of `b` if there are none.
+    Alternative(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(Builtin, Vec<Expr>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum Builtin {
+    Length,
+    Keys,
+    Not,
+    Empty,
+    Sort,
+    Add,
+    Select,
+    Map,
+    SortBy,
+    GroupBy,
+    Has,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str, arity: usize) -> Option<Self> {
+        let builtin = match (name, arity) {
+            ("length", 0) => Builtin::Length,
+            ("keys", 0) => Builtin::Keys,
+            ("not", 0) => Builtin::Not,
+            ("empty", 0) => Builtin::Empty,
+            ("sort", 0) => Builtin::Sort,
+            ("add", 0) => Builtin::Add,
+            ("select", 1) => Builtin::Select,
+            ("map", 1) => Builtin::Map,
+            ("sort_by", 1) => Builtin::SortBy,
+            ("group_by", 1) => Builtin::GroupBy,
+            ("has", 1) => Builtin::Has,
+            _ => return None,
+        };
+        Some(builtin)
+    }
+}
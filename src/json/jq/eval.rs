@@ -0,0 +1,486 @@
+use std::cmp::Ordering;
+
+use super::super::json_value::JSONValue;
+use super::super::object::JSONObject;
+use super::super::ordering::{compare, compare_slices};
+use super::super::serializer::Serializer;
+use super::super::utf16_string::Utf16String;
+use super::super::util::signed_num_64::{ArithmeticOp, SignedNum64};
+use super::ast::{BinOp, Builtin, Expr};
+use super::{FilterError, FilterErrorKind};
+
+type Outputs = Result<Vec<JSONValue>, FilterError>;
+
+/// Receives the outputs of a filter one at a time, so that an error leaves the outputs produced
+/// before it in place for `?` and `//`.
+type Emit<'a> = dyn FnMut(JSONValue) -> Result<(), FilterError> + 'a;
+
+fn type_error(message: String) -> FilterError {
+    FilterError::new(FilterErrorKind::TypeError(message))
+}
+
+/// Describes a value for error messages the way jq does, e.g. `number (1)`.
+fn describe(value: &JSONValue) -> String {
+    let text = Serializer::serialize(value);
+    let text = match text.char_indices().nth(11) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    };
+    format!("{} ({})", value.type_name(), text)
+}
+
+fn truthy(value: &JSONValue) -> bool {
+    !matches!(value, JSONValue::Null | JSONValue::False)
+}
+
+fn boolean(value: bool) -> JSONValue {
+    if value {
+        JSONValue::True
+    } else {
+        JSONValue::False
+    }
+}
+
+fn integer(value: usize) -> JSONValue {
+    JSONValue::Number(SignedNum64::Integer(value as i64))
+}
+
+pub(super) fn eval(expr: &Expr, input: &JSONValue) -> Outputs {
+    let mut outputs = Vec::new();
+    run(expr, input, &mut |value| {
+        outputs.push(value);
+        Ok(())
+    })?;
+    Ok(outputs)
+}
+
+/// Evaluates `expr`, passing each output to `emit` as soon as it is produced.
+fn run(expr: &Expr, input: &JSONValue, emit: &mut Emit) -> Result<(), FilterError> {
+    match expr {
+        Expr::Identity => emit(input.clone()),
+        Expr::Recurse => input.nodes().try_for_each(|(_, v)| emit(v.clone())),
+        Expr::Literal(value) => emit(value.clone()),
+        Expr::Field(target, name) => run(target, input, &mut |value| {
+            emit(index(&value, &JSONValue::String(name.clone()))?)
+        }),
+        Expr::Index(target, key) => run(target, input, &mut |value| {
+            run(key, input, &mut |key| emit(index(&value, &key)?))
+        }),
+        Expr::Slice(target, start, end) => {
+            let bound = |bound: &Option<Box<Expr>>| match bound {
+                Some(expr) => {
+                    eval(expr, input).map(|values| values.into_iter().map(Some).collect())
+                }
+                None => Ok(vec![None]),
+            };
+            let (starts, ends): (Vec<_>, Vec<_>) = (bound(start)?, bound(end)?);
+            run(target, input, &mut |value| {
+                for start in &starts {
+                    for end in &ends {
+                        emit(slice(&value, start.as_ref(), end.as_ref())?)?;
+                    }
+                }
+                Ok(())
+            })
+        }
+        Expr::Iterate(target) => run(target, input, &mut |value| {
+            iterate(&value)?.into_iter().try_for_each(&mut *emit)
+        }),
+        Expr::Try(expr) => {
+            // Errors raised after `?` are not suppressed, so buffer the outputs and emit them
+            // only once the guarded filter has finished or failed.
+            let mut outputs = Vec::new();
+            let _ = run(expr, input, &mut |value| {
+                outputs.push(value);
+                Ok(())
+            });
+            outputs.into_iter().try_for_each(emit)
+        }
+        Expr::Array(None) => emit(JSONValue::Array(Vec::new())),
+        Expr::Array(Some(expr)) => emit(JSONValue::Array(eval(expr, input)?)),
+        Expr::Object(entries) => {
+            let mut objects = vec![JSONObject::new()];
+            for (key, value) in entries {
+                let keys = eval(key, input)?;
+                let values = eval(value, input)?;
+                let mut next = Vec::new();
+                for object in &objects {
+                    for key in &keys {
+                        let JSONValue::String(key) = key else {
+                            return Err(type_error(format!(
+                                "Object keys must be strings, not {}",
+                                describe(key)
+                            )));
+                        };
+                        for value in &values {
+                            let mut object = object.clone();
+                            object.insert(key.as_str(), value.clone());
+                            next.push(object);
+                        }
+                    }
+                }
+                objects = next;
+            }
+            objects
+                .into_iter()
+                .map(JSONValue::Object)
+                .try_for_each(emit)
+        }
+        Expr::Neg(expr) => run(expr, input, &mut |value| match value {
+            JSONValue::Number(SignedNum64::Integer(num)) => emit(match num.checked_neg() {
+                Some(num) => JSONValue::Number(SignedNum64::Integer(num)),
+                None => JSONValue::Number(SignedNum64::Float(-(num as f64))),
+            }),
+            JSONValue::Number(SignedNum64::Float(num)) => {
+                emit(JSONValue::Number(SignedNum64::Float(-num)))
+            }
+            value => Err(type_error(format!(
+                "{} cannot be negated",
+                describe(&value)
+            ))),
+        }),
+        Expr::Pipe(left, right) => run(left, input, &mut |value| run(right, &value, emit)),
+        Expr::Comma(left, right) => {
+            run(left, input, emit)?;
+            run(right, input, emit)
+        }
+        Expr::Alternative(left, right) => {
+            let mut outputs = Vec::new();
+            let _ = run(left, input, &mut |value| {
+                if truthy(&value) {
+                    outputs.push(value);
+                }
+                Ok(())
+            });
+            if outputs.is_empty() {
+                run(right, input, emit)
+            } else {
+                outputs.into_iter().try_for_each(emit)
+            }
+        }
+        Expr::And(left, right) => logical(left, right, input, false, emit),
+        Expr::Or(left, right) => logical(left, right, input, true, emit),
+        Expr::Binary(op, left, right) => {
+            let lefts = eval(left, input)?;
+            // Like jq, the right operand varies slowest.
+            run(right, input, &mut |r| {
+                for l in &lefts {
+                    emit(binary(*op, l, &r)?)?;
+                }
+                Ok(())
+            })
+        }
+        Expr::Call(builtin, args) => call(*builtin, args, input, emit),
+    }
+}
+
+/// Evaluates `and` (`short_circuit` false) or `or` (`short_circuit` true).
+fn logical(
+    left: &Expr,
+    right: &Expr,
+    input: &JSONValue,
+    short_circuit: bool,
+    emit: &mut Emit,
+) -> Result<(), FilterError> {
+    run(left, input, &mut |l| {
+        if truthy(&l) == short_circuit {
+            return emit(boolean(short_circuit));
+        }
+        run(right, input, &mut |r| emit(boolean(truthy(&r))))
+    })
+}
+
+fn index(value: &JSONValue, key: &JSONValue) -> Result<JSONValue, FilterError> {
+    match (value, key) {
+        (JSONValue::Null, JSONValue::String(_) | JSONValue::Number(_)) => Ok(JSONValue::Null),
+        (JSONValue::Object(obj), JSONValue::String(key)) => {
+            Ok(obj.get(key).cloned().unwrap_or(JSONValue::Null))
+        }
+        (JSONValue::Array(arr), JSONValue::Number(num)) => {
+            let len = arr.len() as i64;
+            let i = num.as_f64().floor() as i64;
+            let i = if i < 0 { len + i } else { i };
+            Ok(usize::try_from(i)
+                .ok()
+                .and_then(|i| arr.get(i))
+                .cloned()
+                .unwrap_or(JSONValue::Null))
+        }
+        _ => Err(type_error(format!(
+            "Cannot index {} with {}",
+            value.type_name(),
+            match key {
+                JSONValue::String(key) => format!("\"{}\"", key),
+                key => key.type_name().to_string(),
+            }
+        ))),
+    }
+}
+
+fn slice(
+    value: &JSONValue,
+    start: Option<&JSONValue>,
+    end: Option<&JSONValue>,
+) -> Result<JSONValue, FilterError> {
+    let bound = |bound: Option<&JSONValue>, len: usize, default: usize| match bound {
+        None | Some(JSONValue::Null) => Ok(default),
+        Some(JSONValue::Number(num)) => {
+            let i = num.as_f64().floor() as i64;
+            let i = if i < 0 { len as i64 + i } else { i };
+            Ok(i.clamp(0, len as i64) as usize)
+        }
+        Some(other) => Err(type_error(format!(
+            "Start and end indices of a slice must be numbers, not {}",
+            describe(other)
+        ))),
+    };
+
+    match value {
+        JSONValue::Null => Ok(JSONValue::Null),
+        JSONValue::Array(arr) => {
+            let start = bound(start, arr.len(), 0)?;
+            let end = bound(end, arr.len(), arr.len())?.max(start);
+            Ok(JSONValue::Array(arr[start..end].to_vec()))
+        }
+        JSONValue::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let start = bound(start, chars.len(), 0)?;
+            let end = bound(end, chars.len(), chars.len())?.max(start);
+            Ok(JSONValue::String(chars[start..end].iter().collect()))
+        }
+        value => Err(type_error(format!(
+            "Cannot index {} with object",
+            value.type_name()
+        ))),
+    }
+}
+
+fn iterate(value: &JSONValue) -> Outputs {
+    match value {
+        JSONValue::Array(arr) => Ok(arr.clone()),
+        JSONValue::Object(obj) => Ok(obj.values().cloned().collect()),
+        value => Err(type_error(format!(
+            "Cannot iterate over {}",
+            describe(value)
+        ))),
+    }
+}
+
+fn arithmetic(
+    op: BinOp,
+    left: SignedNum64,
+    right: SignedNum64,
+) -> Result<SignedNum64, FilterError> {
+    let op = match op {
+        BinOp::Add => ArithmeticOp::Add,
+        BinOp::Sub => ArithmeticOp::Sub,
+        BinOp::Mul => ArithmeticOp::Mul,
+        BinOp::Div => ArithmeticOp::Div,
+        _ => ArithmeticOp::Mod,
+    };
+    left.arithmetic(op, right).ok_or_else(|| {
+        type_error(format!(
+            "{} and {} cannot be divided because the divisor is zero",
+            describe(&JSONValue::Number(left)),
+            describe(&JSONValue::Number(right))
+        ))
+    })
+}
+
+fn binary(op: BinOp, left: &JSONValue, right: &JSONValue) -> Result<JSONValue, FilterError> {
+    let ordering = || compare(left, right);
+    let result = match (op, left, right) {
+        (BinOp::Eq, _, _) => boolean(ordering() == Ordering::Equal),
+        (BinOp::Ne, _, _) => boolean(ordering() != Ordering::Equal),
+        (BinOp::Lt, _, _) => boolean(ordering() == Ordering::Less),
+        (BinOp::Le, _, _) => boolean(ordering() != Ordering::Greater),
+        (BinOp::Gt, _, _) => boolean(ordering() == Ordering::Greater),
+        (BinOp::Ge, _, _) => boolean(ordering() != Ordering::Less),
+        (_, JSONValue::Number(l), JSONValue::Number(r)) => {
+            JSONValue::Number(arithmetic(op, *l, *r)?)
+        }
+        (BinOp::Add, JSONValue::Null, value) | (BinOp::Add, value, JSONValue::Null) => {
+            value.clone()
+        }
+        (BinOp::Add, JSONValue::String(l), JSONValue::String(r)) => {
+            JSONValue::String(format!("{}{}", l, r))
+        }
+        (
+            BinOp::Add,
+            JSONValue::String(_) | JSONValue::Utf16String(_),
+            JSONValue::String(_) | JSONValue::Utf16String(_),
+        ) => {
+            // Join the code units, so that lone surrogates survive or pair up.
+            let units = |value: &JSONValue| match value {
+                JSONValue::Utf16String(s) => s.units().to_vec(),
+                value => value.as_str().unwrap().encode_utf16().collect(),
+            };
+            match Utf16String::from_units([units(left), units(right)].concat()).into_string() {
+                Ok(s) => JSONValue::String(s),
+                Err(s) => JSONValue::Utf16String(s),
+            }
+        }
+        (BinOp::Add, JSONValue::Array(l), JSONValue::Array(r)) => {
+            JSONValue::Array(l.iter().chain(r).cloned().collect())
+        }
+        (BinOp::Add, JSONValue::Object(l), JSONValue::Object(r)) => {
+            let mut merged = l.clone();
+            merged.extend(r.iter().map(|(k, v)| (k.clone(), v.clone())));
+            JSONValue::Object(merged)
+        }
+        (BinOp::Sub, JSONValue::Array(l), JSONValue::Array(r)) => JSONValue::Array(
+            l.iter()
+                .filter(|v| !r.iter().any(|x| compare(v, x) == Ordering::Equal))
+                .cloned()
+                .collect(),
+        ),
+        (BinOp::Div, JSONValue::String(l), JSONValue::String(r)) => JSONValue::Array(
+            l.split(r.as_str())
+                .map(|s| JSONValue::String(s.to_string()))
+                .collect(),
+        ),
+        _ => {
+            let verb = match op {
+                BinOp::Add => "added",
+                BinOp::Sub => "subtracted",
+                BinOp::Mul => "multiplied",
+                BinOp::Div => "divided",
+                _ => "divided (remainder)",
+            };
+            return Err(type_error(format!(
+                "{} and {} cannot be {}",
+                describe(left),
+                describe(right),
+                verb
+            )));
+        }
+    };
+    Ok(result)
+}
+
+fn sort_by_keys(
+    values: Vec<JSONValue>,
+    f: &Expr,
+) -> Result<Vec<(Vec<JSONValue>, JSONValue)>, FilterError> {
+    let mut keyed = values
+        .into_iter()
+        .map(|value| Ok((eval(f, &value)?, value)))
+        .collect::<Result<Vec<_>, FilterError>>()?;
+    keyed.sort_by(|(a, _), (b, _)| compare_slices(a, b));
+    Ok(keyed)
+}
+
+fn array_input<'a>(builtin: &str, input: &'a JSONValue) -> Result<&'a Vec<JSONValue>, FilterError> {
+    input.as_array().ok_or_else(|| {
+        type_error(format!(
+            "Cannot {} {}, as it is not an array",
+            builtin,
+            describe(input)
+        ))
+    })
+}
+
+fn call(
+    builtin: Builtin,
+    args: &[Expr],
+    input: &JSONValue,
+    emit: &mut Emit,
+) -> Result<(), FilterError> {
+    let output = match builtin {
+        Builtin::Length => match input {
+            JSONValue::Null => integer(0),
+            JSONValue::Number(SignedNum64::Integer(num)) => {
+                JSONValue::Number(SignedNum64::Integer(num.saturating_abs()))
+            }
+            JSONValue::Number(SignedNum64::Float(num)) => {
+                JSONValue::Number(SignedNum64::Float(num.abs()))
+            }
+            JSONValue::String(s) => integer(s.chars().count()),
+            JSONValue::Utf16String(s) => integer(s.to_string_lossy().chars().count()),
+            JSONValue::Array(arr) => integer(arr.len()),
+            JSONValue::Object(obj) => integer(obj.len()),
+            value => return Err(type_error(format!("{} has no length", describe(value)))),
+        },
+        Builtin::Keys => match input {
+            JSONValue::Object(obj) => {
+                let mut keys: Vec<_> = obj.keys().collect();
+                keys.sort();
+                JSONValue::Array(keys.into_iter().map(|k| JSONValue::from(&**k)).collect())
+            }
+            JSONValue::Array(arr) => JSONValue::Array((0..arr.len()).map(integer).collect()),
+            value => return Err(type_error(format!("{} has no keys", describe(value)))),
+        },
+        Builtin::Not => boolean(!truthy(input)),
+        Builtin::Empty => return Ok(()),
+        Builtin::Sort => {
+            let mut values = array_input("sort", input)?.clone();
+            values.sort_by(compare);
+            JSONValue::Array(values)
+        }
+        Builtin::Add => {
+            let values = match input {
+                JSONValue::Object(obj) => obj.values().cloned().collect(),
+                input => array_input("add", input)?.clone(),
+            };
+            values.iter().try_fold(JSONValue::Null, |sum, value| {
+                binary(BinOp::Add, &sum, value)
+            })?
+        }
+        Builtin::Select => {
+            return run(&args[0], input, &mut |value| {
+                if truthy(&value) {
+                    emit(input.clone())?;
+                }
+                Ok(())
+            });
+        }
+        Builtin::Map => {
+            let mut outputs = Vec::new();
+            for value in iterate(input)? {
+                outputs.extend(eval(&args[0], &value)?);
+            }
+            JSONValue::Array(outputs)
+        }
+        Builtin::SortBy => {
+            let values = array_input("sort", input)?.clone();
+            let sorted = sort_by_keys(values, &args[0])?;
+            JSONValue::Array(sorted.into_iter().map(|(_, value)| value).collect())
+        }
+        Builtin::GroupBy => {
+            let values = array_input("group", input)?.clone();
+            let mut groups: Vec<(Vec<JSONValue>, Vec<JSONValue>)> = Vec::new();
+            for (key, value) in sort_by_keys(values, &args[0])? {
+                match groups.last_mut() {
+                    Some((last, group)) if compare_slices(last, &key).is_eq() => group.push(value),
+                    _ => groups.push((key, vec![value])),
+                }
+            }
+            JSONValue::Array(
+                groups
+                    .into_iter()
+                    .map(|(_, group)| JSONValue::Array(group))
+                    .collect(),
+            )
+        }
+        Builtin::Has => {
+            return run(&args[0], input, &mut |key| {
+                let has = match (input, &key) {
+                    (JSONValue::Object(obj), JSONValue::String(key)) => obj.contains_key(key),
+                    (JSONValue::Array(arr), JSONValue::Number(num)) => {
+                        let i = num.as_f64();
+                        i >= 0.0 && (i as usize) < arr.len()
+                    }
+                    _ => {
+                        return Err(type_error(format!(
+                            "Cannot check whether {} has a {} key",
+                            input.type_name(),
+                            key.type_name()
+                        )))
+                    }
+                };
+                emit(boolean(has))
+            });
+        }
+    };
+    emit(output)
+}
@@ -0,0 +1,278 @@
+mod ast;
+mod eval;
+mod parser;
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::json_value::JSONValue;
+use ast::Expr;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FilterErrorKind {
+    UnexpectedCharacter,
+    UnexpectedToken,
+    UnexpectedEnd,
+    InvalidEscape,
+    InvalidNumber,
+    /// The function is not a supported builtin, given as `name/arity`.
+    UnknownFunction(String),
+    /// A value of the wrong type was used at run time, e.g. `"a" - 1`.
+    TypeError(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FilterError {
+    kind: FilterErrorKind,
+    position: Option<usize>,
+}
+
+impl FilterError {
+    pub fn new(kind: FilterErrorKind) -> Self {
+        FilterError {
+            kind,
+            position: None,
+        }
+    }
+
+    /// Creates an error located at `position`, a byte offset into the filter text.
+    pub fn with_position(kind: FilterErrorKind, position: usize) -> Self {
+        FilterError {
+            kind,
+            position: Some(position),
+        }
+    }
+
+    pub fn kind(&self) -> &FilterErrorKind {
+        &self.kind
+    }
+
+    /// The position of a syntax error; run-time errors have none.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{:?} at position {}", self.kind, position),
+            None => write!(f, "{:?}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// A compiled jq filter such as `.users[] | select(.age >= 18) | .name`.
+///
+/// Supports a subset of jq: `.`, `..`, field, index and slice access, `.[]`, `?`, pipes, `,`,
+/// array and object construction, arithmetic, comparison, `and`/`or`, `//`, and the builtins
+/// `length`, `keys`, `not`, `empty`, `sort`, `add`, `select`, `map`, `sort_by`, `group_by` and
+/// `has`.
+///
+/// ```
+/// use rust_json::json;
+/// use rust_json::json::jq::Filter;
+///
+/// let value = json!({"users": [{"name": "Alice", "age": 15}, {"name": "Bob", "age": 42}]});
+/// let filter = Filter::parse(".users[] | select(.age >= 18) | {name, adult: true}").unwrap();
+/// assert_eq!(
+///     vec![json!({"name": "Bob", "adult": true})],
+///     filter.apply(&value).unwrap()
+/// );
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Filter(Expr);
+
+impl Filter {
+    pub fn parse(text: &str) -> Result<Self, FilterError> {
+        parser::Parser::parse(text).map(Self)
+    }
+
+    /// Runs the filter on `input` and returns all of its outputs in order.
+    pub fn apply(&self, input: &JSONValue) -> Result<Vec<JSONValue>, FilterError> {
+        eval::eval(&self.0, input)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl JSONValue {
+    /// Parses `filter` as a jq filter and applies it to this value; see `Filter`.
+    pub fn jq(&self, filter: &str) -> Result<Vec<JSONValue>, FilterError> {
+        Filter::parse(filter)?.apply(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::utf16_string::Utf16String;
+    use super::*;
+    use crate::json;
+
+    fn run(value: &JSONValue, filter: &str) -> Vec<JSONValue> {
+        value.jq(filter).unwrap()
+    }
+
+    #[test]
+    fn access() {
+        let value = json!({"a": {"b": [1, 2, 3]}, "c d": null});
+        assert_eq!(vec![value.clone()], run(&value, "."));
+        assert_eq!(vec![json!([1, 2, 3])], run(&value, ".a.b"));
+        assert_eq!(vec![json!(3)], run(&value, ".a.b[-1]"));
+        assert_eq!(vec![json!([2, 3])], run(&value, ".a.b[1:]"));
+        assert_eq!(vec![json!(null)], run(&value, ".\"c d\""));
+        assert_eq!(vec![json!(null)], run(&value, ".missing.deeper"));
+        assert_eq!(vec![json!(1), json!(2), json!(3)], run(&value, ".a.b[]"));
+        assert_eq!(vec![json!(1)], run(&value, ".a[\"b\"][0]"));
+        assert_eq!(7, run(&value, "..").len());
+        assert!(run(&json!([1]), ".a?").is_empty());
+    }
+
+    #[test]
+    fn pipes() {
+        let value = json!([
+            {"name": "Alice", "age": 15},
+            {"name": "Bob", "age": 42},
+            {"name": "Carol", "age": 30}
+        ]);
+        assert_eq!(
+            vec![json!("Bob"), json!("Carol")],
+            run(&value, ".[] | select(.age > 18) | .name")
+        );
+        assert_eq!(vec![json!([16, 43, 31])], run(&value, "map(.age + 1)"));
+        assert_eq!(
+            vec![json!("Alice"), json!(15)],
+            run(&value, ".[0] | .name, .age")
+        );
+        assert_eq!(vec![json!(87)], run(&value, "map(.age) | add"));
+    }
+
+    #[test]
+    fn construction() {
+        let value = json!({"name": "Bob", "tags": ["a", "b"]});
+        assert_eq!(
+            vec![json!({"name": "Bob", "count": 2})],
+            run(&value, "{name, count: .tags | length}")
+        );
+        assert_eq!(
+            vec![json!({"tag": "a"}), json!({"tag": "b"})],
+            run(&value, "{tag: .tags[]}")
+        );
+        assert_eq!(
+            vec![json!({"Bob": ["a", "b"]})],
+            run(&value, "{(.name): .tags}")
+        );
+        assert_eq!(
+            vec![json!(["Bob", "a", "b"])],
+            run(&value, "[.name, .tags[]]")
+        );
+        assert_eq!(vec![json!([])], run(&value, "[]"));
+    }
+
+    #[test]
+    fn operators() {
+        let value = json!({"a": 7, "b": 2});
+        assert_eq!(vec![json!(9)], run(&value, ".a + .b"));
+        assert_eq!(vec![json!(3.5)], run(&value, ".a / .b"));
+        assert_eq!(vec![json!(1)], run(&value, ".a % .b"));
+        assert_eq!(vec![json!(11)], run(&value, ".a + .b * 2"));
+        assert_eq!(vec![json!(-5)], run(&value, ".b - .a"));
+        let min = json!({"a": i64::MIN});
+        assert_eq!(vec![json!(-(i64::MIN as f64))], run(&min, ".a / -1"));
+        assert_eq!(vec![json!(0)], run(&min, ".a % -1"));
+        assert_eq!(vec![json!("ab")], run(&value, "\"a\" + \"b\""));
+        let lone = |units: Vec<u16>| JSONValue::Utf16String(Utf16String::from_units(units));
+        let halves = json!({"a": lone(vec![0x61, 0xd83d]), "b": lone(vec![0xde00])});
+        assert_eq!(vec![json!("a😀")], run(&halves, ".a + .b"));
+        assert_eq!(
+            vec![lone(vec![0x61, 0xd83d, 0x62])],
+            run(&halves, ".a + \"b\"")
+        );
+        assert_eq!(vec![json!([1, 2])], run(&value, "[1] + [2]"));
+        assert_eq!(
+            vec![json!({"a": 1, "b": 2})],
+            run(&value, "{a: 1} + {b: 2}")
+        );
+        assert_eq!(vec![json!(true)], run(&value, ".a > .b and .b >= 2"));
+        assert_eq!(vec![json!(false)], run(&value, ".a == .b or (.a < 0)"));
+        assert_eq!(vec![json!(true)], run(&value, "1 == 1.0"));
+        assert_eq!(vec![json!(true)], run(&value, "null < false"));
+        assert_eq!(vec![json!(true)], run(&value, ".missing | not"));
+    }
+
+    #[test]
+    fn builtins() {
+        let value = json!([
+            {"name": "Carol", "team": "b"},
+            {"name": "Alice", "team": "a"},
+            {"name": "Bob", "team": "b"}
+        ]);
+        assert_eq!(vec![json!(3)], run(&value, "length"));
+        assert_eq!(vec![json!(5)], run(&value, ".[0].name | length"));
+        assert_eq!(vec![json!(["name", "team"])], run(&value, ".[0] | keys"));
+        assert_eq!(
+            vec![json!(["Alice", "Bob", "Carol"])],
+            run(&value, "sort_by(.name) | map(.name)")
+        );
+        assert_eq!(
+            vec![json!([["Alice"], ["Carol", "Bob"]])],
+            run(&value, "group_by(.team) | map(map(.name))")
+        );
+        assert_eq!(vec![json!([1, 2, 3])], run(&json!([3, 1, 2]), "sort"));
+        assert_eq!(vec![json!(true)], run(&value, ".[0] | has(\"team\")"));
+        assert!(run(&value, "empty").is_empty());
+    }
+
+    #[test]
+    fn alternative() {
+        let value = json!({"a": null, "b": false, "c": 1});
+        assert_eq!(vec![json!(1)], run(&value, ".a // .b // .c"));
+        assert_eq!(
+            vec![json!("default")],
+            run(&value, ".missing // \"default\"")
+        );
+        assert_eq!(vec![json!(1)], run(&value, "(.a, .c) // 2"));
+        assert_eq!(vec![json!(0)], run(&value, ".x.y.z // 0"));
+        assert_eq!(vec![json!(1)], run(&value, "(.c, (.c | .[0])) // 2"));
+    }
+
+    #[test]
+    fn optional() {
+        let value = json!({"a": 1, "b": "x"});
+        assert_eq!(vec![json!([1])], run(&value, "[(.a, .b - 1)?]"));
+        assert_eq!(
+            vec![json!([2])],
+            run(&json!([1, "x", 3]), "[(.[] | . + 1)?]")
+        );
+        assert!(value.jq("(.a, .b)? | . - 1").is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let kind = |filter: &str| Filter::parse(filter).unwrap_err().kind().clone();
+        assert_eq!(FilterErrorKind::UnexpectedEnd, kind(".a |"));
+        assert_eq!(FilterErrorKind::UnexpectedCharacter, kind(".a $"));
+        assert_eq!(FilterErrorKind::UnexpectedToken, kind(".a )"));
+        assert_eq!(FilterErrorKind::InvalidEscape, kind("\"\\x\""));
+        assert_eq!(
+            FilterErrorKind::UnknownFunction("foo/1".into()),
+            kind("foo(.)")
+        );
+
+        let error = json!({"a": "x"}).jq(".a - 1").unwrap_err();
+        assert!(matches!(error.kind(), FilterErrorKind::TypeError(_)));
+        assert_eq!(None, error.position());
+        assert!(json!(1).jq(".[0]").is_err());
+
+        let error = Filter::parse(".a | )").unwrap_err();
+        assert_eq!("UnexpectedToken at position 5", error.to_string());
+    }
+}
@@ -0,0 +1,505 @@
+use super::super::json_value::JSONValue;
+use super::super::parser_error::ParserErrorKind;
+use super::super::parser_options::{LoneSurrogates, StringValidation};
+use super::super::string::{unescape, Unescaped};
+use super::super::util::signed_num_64::SignedNum64;
+use super::ast::{BinOp, Builtin, Expr};
+use super::{FilterError, FilterErrorKind};
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Dot,
+    DotDot,
+    /// `.name`
+    Field(String),
+    Ident(String),
+    String(String),
+    Number(SignedNum64),
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    Pipe,
+    Comma,
+    Colon,
+    Semicolon,
+    Question,
+    Alternative,
+    Op(BinOp),
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, FilterError> {
+    let error = |kind, position| FilterError::with_position(kind, position);
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while let Some(c) = text[pos..].chars().next() {
+        let start = pos;
+        let rest = &text[pos..];
+        let two = rest.get(..2).unwrap_or("");
+
+        let token = if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        } else if two == ".." {
+            pos += 2;
+            Token::DotDot
+        } else if c == '.' && rest[1..].starts_with(is_ident_start) {
+            let len = rest[1..]
+                .find(|c| !is_ident_char(c))
+                .unwrap_or(rest.len() - 1);
+            pos += 1 + len;
+            Token::Field(rest[1..1 + len].to_string())
+        } else if is_ident_start(c) {
+            let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+            pos += len;
+            Token::Ident(rest[..len].to_string())
+        } else if c.is_ascii_digit() {
+            let (num, len) =
+                number(rest).ok_or_else(|| error(FilterErrorKind::InvalidNumber, start))?;
+            pos += len;
+            Token::Number(num)
+        } else if c == '"' {
+            let (s, len) = string(rest).map_err(|(kind, offset)| error(kind, start + offset))?;
+            pos += len;
+            Token::String(s)
+        } else {
+            let (token, len) = match two {
+                "//" => (Token::Alternative, 2),
+                "==" => (Token::Op(BinOp::Eq), 2),
+                "!=" => (Token::Op(BinOp::Ne), 2),
+                "<=" => (Token::Op(BinOp::Le), 2),
+                ">=" => (Token::Op(BinOp::Ge), 2),
+                _ => {
+                    let token = match c {
+                        '.' => Token::Dot,
+                        '[' => Token::LBracket,
+                        ']' => Token::RBracket,
+                        '{' => Token::LBrace,
+                        '}' => Token::RBrace,
+                        '(' => Token::LParen,
+                        ')' => Token::RParen,
+                        '|' => Token::Pipe,
+                        ',' => Token::Comma,
+                        ':' => Token::Colon,
+                        ';' => Token::Semicolon,
+                        '?' => Token::Question,
+                        '<' => Token::Op(BinOp::Lt),
+                        '>' => Token::Op(BinOp::Gt),
+                        '+' => Token::Op(BinOp::Add),
+                        '-' => Token::Op(BinOp::Sub),
+                        '*' => Token::Op(BinOp::Mul),
+                        '/' => Token::Op(BinOp::Div),
+                        '%' => Token::Op(BinOp::Mod),
+                        _ => return Err(error(FilterErrorKind::UnexpectedCharacter, start)),
+                    };
+                    (token, 1)
+                }
+            };
+            pos += len;
+            token
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+/// Reads a number literal at the start of `text`, returning it with its length.
+fn number(text: &str) -> Option<(SignedNum64, usize)> {
+    let bytes = text.as_bytes();
+    let digits = |from: usize| {
+        from + bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let mut len = digits(0);
+    let mut is_float = false;
+    if bytes.get(len) == Some(&b'.') {
+        is_float = true;
+        len = digits(len + 1);
+    }
+    if let Some(b'e' | b'E') = bytes.get(len) {
+        is_float = true;
+        len += 1;
+        if let Some(b'+' | b'-') = bytes.get(len) {
+            len += 1;
+        }
+        let end = digits(len);
+        if end == len {
+            return None;
+        }
+        len = end;
+    }
+
+    let literal = &text[..len];
+    let num = match literal.parse::<i64>() {
+        Ok(num) if !is_float => SignedNum64::Integer(num),
+        _ => SignedNum64::Float(literal.parse().ok()?),
+    };
+    Some((num, len))
+}
+
+/// Reads a string literal at the start of `text`, returning its value and length.
+fn string(text: &str) -> Result<(String, usize), (FilterErrorKind, usize)> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => {
+                let contents = &text[1..i];
+                return match unescape(contents, StringValidation::Strict, LoneSurrogates::Replace) {
+                    Ok(Unescaped::String(s)) => Ok((s, i + 1)),
+                    Ok(Unescaped::Utf16String(_)) => unreachable!(),
+                    Err((ParserErrorKind::InvalidEscape, offset)) => {
+                        Err((FilterErrorKind::InvalidEscape, 1 + offset))
+                    }
+                    Err((_, offset)) => Err((FilterErrorKind::UnexpectedCharacter, 1 + offset)),
+                };
+            }
+            _ => {}
+        }
+    }
+    Err((FilterErrorKind::UnexpectedEnd, text.len()))
+}
+
+pub(super) struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    len: usize,
+}
+
+impl Parser {
+    pub fn parse(text: &str) -> Result<Expr, FilterError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            index: 0,
+            len: text.len(),
+        };
+        let expr = parser.pipe()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(_) => Err(parser.unexpected()),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.index + offset).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(token, _)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), FilterError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map_or(self.len, |(_, position)| *position)
+    }
+
+    fn unexpected(&self) -> FilterError {
+        let kind = match self.peek() {
+            Some(_) => FilterErrorKind::UnexpectedToken,
+            None => FilterErrorKind::UnexpectedEnd,
+        };
+        FilterError::with_position(kind, self.position())
+    }
+
+    fn pipe(&mut self) -> Result<Expr, FilterError> {
+        let left = self.comma()?;
+        if self.eat(&Token::Pipe) {
+            return Ok(Expr::Pipe(Box::new(left), Box::new(self.pipe()?)));
+        }
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.alternative()?;
+        while self.eat(&Token::Comma) {
+            left = Expr::Comma(Box::new(left), Box::new(self.alternative()?));
+        }
+        Ok(left)
+    }
+
+    fn alternative(&mut self) -> Result<Expr, FilterError> {
+        let left = self.or()?;
+        if self.eat(&Token::Alternative) {
+            return Ok(Expr::Alternative(
+                Box::new(left),
+                Box::new(self.alternative()?),
+            ));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.and()?;
+        while self.eat(&Token::Ident("or".into())) {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.comparison()?;
+        while self.eat(&Token::Ident("and".into())) {
+            left = Expr::And(Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, FilterError> {
+        let left = self.additive()?;
+        match self.peek() {
+            Some(Token::Op(
+                op @ (BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge),
+            )) => {
+                let op = *op;
+                self.index += 1;
+                let right = self.additive()?;
+                Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.multiplicative()?;
+        while let Some(Token::Op(op @ (BinOp::Add | BinOp::Sub))) = self.peek() {
+            let op = *op;
+            self.index += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.multiplicative()?));
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op @ (BinOp::Mul | BinOp::Div | BinOp::Mod))) = self.peek() {
+            let op = *op;
+            self.index += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        if self.eat(&Token::Op(BinOp::Sub)) {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.term()?;
+        loop {
+            expr = match self.peek() {
+                Some(Token::Field(name)) => {
+                    let name = name.clone();
+                    self.index += 1;
+                    Expr::Field(Box::new(expr), name)
+                }
+                Some(Token::Dot) if matches!(self.peek_at(1), Some(Token::String(_))) => {
+                    self.index += 1;
+                    let Some(Token::String(name)) = self.next() else {
+                        unreachable!()
+                    };
+                    Expr::Field(Box::new(expr), name)
+                }
+                Some(Token::Dot) if self.peek_at(1) == Some(&Token::LBracket) => {
+                    self.index += 1;
+                    self.bracket_suffix(expr)?
+                }
+                Some(Token::LBracket) => self.bracket_suffix(expr)?,
+                Some(Token::Question) => {
+                    self.index += 1;
+                    Expr::Try(Box::new(expr))
+                }
+                _ => return Ok(expr),
+            };
+        }
+    }
+
+    /// Parses `[]`, `[index]` or `[start:end]` after `expr`.
+    fn bracket_suffix(&mut self, expr: Expr) -> Result<Expr, FilterError> {
+        self.expect(&Token::LBracket)?;
+        let target = Box::new(expr);
+        if self.eat(&Token::RBracket) {
+            return Ok(Expr::Iterate(target));
+        }
+
+        let start = match self.peek() {
+            Some(Token::Colon) => None,
+            _ => Some(Box::new(self.pipe()?)),
+        };
+        let result = if self.eat(&Token::Colon) {
+            let end = match self.peek() {
+                Some(Token::RBracket) => None,
+                _ => Some(Box::new(self.pipe()?)),
+            };
+            Expr::Slice(target, start, end)
+        } else {
+            match start {
+                Some(index) => Expr::Index(target, index),
+                None => return Err(self.unexpected()),
+            }
+        };
+        self.expect(&Token::RBracket)?;
+        Ok(result)
+    }
+
+    fn term(&mut self) -> Result<Expr, FilterError> {
+        let position = self.position();
+        let Some(token) = self.next() else {
+            return Err(FilterError::with_position(
+                FilterErrorKind::UnexpectedEnd,
+                position,
+            ));
+        };
+
+        match token {
+            // `.[...]` is handled as a suffix of the identity.
+            Token::Dot => match self.peek() {
+                Some(Token::String(_)) => {
+                    let Some(Token::String(name)) = self.next() else {
+                        unreachable!()
+                    };
+                    Ok(Expr::Field(Box::new(Expr::Identity), name))
+                }
+                _ => Ok(Expr::Identity),
+            },
+            Token::DotDot => Ok(Expr::Recurse),
+            Token::Field(name) => Ok(Expr::Field(Box::new(Expr::Identity), name)),
+            Token::Number(num) => Ok(Expr::Literal(JSONValue::Number(num))),
+            Token::String(s) => Ok(Expr::Literal(JSONValue::String(s))),
+            Token::LParen => {
+                let expr = self.pipe()?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Token::LBracket => {
+                if self.eat(&Token::RBracket) {
+                    return Ok(Expr::Array(None));
+                }
+                let expr = self.pipe()?;
+                self.expect(&Token::RBracket)?;
+                Ok(Expr::Array(Some(Box::new(expr))))
+            }
+            Token::LBrace => self.object(),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(JSONValue::True)),
+                "false" => Ok(Expr::Literal(JSONValue::False)),
+                "null" => Ok(Expr::Literal(JSONValue::Null)),
+                _ => self.call(name, position),
+            },
+            _ => {
+                self.index -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+
+    fn call(&mut self, name: String, position: usize) -> Result<Expr, FilterError> {
+        let mut args = Vec::new();
+        if self.eat(&Token::LParen) {
+            loop {
+                args.push(self.pipe()?);
+                if !self.eat(&Token::Semicolon) {
+                    break;
+                }
+            }
+            self.expect(&Token::RParen)?;
+        }
+        match Builtin::from_name(&name, args.len()) {
+            Some(builtin) => Ok(Expr::Call(builtin, args)),
+            None => Err(FilterError::with_position(
+                FilterErrorKind::UnknownFunction(format!("{}/{}", name, args.len())),
+                position,
+            )),
+        }
+    }
+
+    /// Parses the entries of `{...}` after the opening brace.
+    fn object(&mut self) -> Result<Expr, FilterError> {
+        let mut entries = Vec::new();
+        if self.eat(&Token::RBrace) {
+            return Ok(Expr::Object(entries));
+        }
+        loop {
+            let (key, shorthand) = match self.next() {
+                Some(Token::Ident(name) | Token::String(name)) => {
+                    (Expr::Literal(JSONValue::String(name.clone())), Some(name))
+                }
+                Some(Token::LParen) => {
+                    let key = self.pipe()?;
+                    self.expect(&Token::RParen)?;
+                    (key, None)
+                }
+                _ => {
+                    self.index -= 1;
+                    return Err(self.unexpected());
+                }
+            };
+            let value = if self.eat(&Token::Colon) {
+                self.object_value()?
+            } else {
+                match shorthand {
+                    Some(name) => Expr::Field(Box::new(Expr::Identity), name),
+                    None => return Err(self.unexpected()),
+                }
+            };
+            entries.push((key, value));
+
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace)?;
+        Ok(Expr::Object(entries))
+    }
+
+    /// Parses an object value, which like in jq is a pipe of terms: `{a: .b | length}` is
+    /// allowed, while `{a: .b + 1}` needs parentheses.
+    fn object_value(&mut self) -> Result<Expr, FilterError> {
+        let left = self.unary()?;
+        if self.eat(&Token::Pipe) {
+            return Ok(Expr::Pipe(Box::new(left), Box::new(self.object_value()?)));
+        }
+        Ok(left)
+    }
+}
@@ -0,0 +1,131 @@
+use super::super::json_value::{JSONValue, Key};
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) struct Query {
+    /// Whether the query starts at the document root `$` rather than the current node `@`.
+    pub absolute: bool,
+    pub segments: Vec<Segment>,
+}
+
+impl Query {
+    /// Whether the query selects at most one node: only child segments with a single name or
+    /// index selector each.
+    pub fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            !segment.descendant
+                && matches!(
+                    segment.selectors.as_slice(),
+                    [Selector::Name(_)] | [Selector::Index(_)]
+                )
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) struct Segment {
+    /// A `..` segment, which applies the selectors to the input node and all its descendants.
+    pub descendant: bool,
+    pub selectors: Vec<Selector>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) enum Selector {
+    Name(Key),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Expr),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Comparison(Comparable, CmpOp, Comparable),
+    /// A filter query that is true if it selects any node.
+    Exists(Query),
+    /// A call to a function returning a logical value.
+    Function(Call),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) enum Comparable {
+    Literal(JSONValue),
+    /// A singular query.
+    Query(Query),
+    /// A call to a function returning a value.
+    Function(Call),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum Function {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+/// The types of function parameters and results defined by RFC 9535.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum Type {
+    Value,
+    Logical,
+    Nodes,
+}
+
+impl Function {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "length" => Some(Function::Length),
+            "count" => Some(Function::Count),
+            "match" => Some(Function::Match),
+            "search" => Some(Function::Search),
+            "value" => Some(Function::Value),
+            _ => None,
+        }
+    }
+
+    pub fn parameters(self) -> &'static [Type] {
+        match self {
+            Function::Length => &[Type::Value],
+            Function::Count | Function::Value => &[Type::Nodes],
+            Function::Match | Function::Search => &[Type::Value, Type::Value],
+        }
+    }
+
+    pub fn result(self) -> Type {
+        match self {
+            Function::Length | Function::Count | Function::Value => Type::Value,
+            Function::Match | Function::Search => Type::Logical,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) struct Call {
+    pub function: Function,
+    pub args: Vec<Argument>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) enum Argument {
+    Literal(JSONValue),
+    Query(Query),
+    Function(Call),
+}
@@ -0,0 +1,307 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use super::super::json_value::JSONValue;
+use super::super::ordering;
+use super::super::path::{Path, PathSegment};
+use super::super::util::signed_num_64::SignedNum64;
+use super::ast::{Argument, Call, CmpOp, Comparable, Expr, Function, Query, Selector};
+
+pub(super) type Node<'a> = (Path, &'a JSONValue);
+
+/// A value operand of a filter: `None` is the special result `Nothing` of an empty query.
+type Operand<'a> = Option<Cow<'a, JSONValue>>;
+
+pub(super) struct Evaluator<'a> {
+    root: &'a JSONValue,
+    /// Compiled regular expressions by pattern and anchoring, or `None` for invalid patterns.
+    regexes: RefCell<HashMap<(String, bool), Option<Regex>>>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(root: &'a JSONValue) -> Self {
+        Self {
+            root,
+            regexes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn query(&self, query: &Query, current: &'a JSONValue) -> Vec<Node<'a>> {
+        let start = if query.absolute { self.root } else { current };
+        let mut nodes = vec![(Path::root(), start)];
+        for segment in &query.segments {
+            let mut selected = Vec::new();
+            for (path, value) in &nodes {
+                if segment.descendant {
+                    self.descend(path, value, &segment.selectors, &mut selected);
+                } else {
+                    for selector in &segment.selectors {
+                        self.select(selector, path, value, &mut selected);
+                    }
+                }
+            }
+            nodes = selected;
+        }
+        nodes
+    }
+
+    /// Applies the selectors to `value` and then to each of its descendants, in document order.
+    fn descend(
+        &self,
+        path: &Path,
+        value: &'a JSONValue,
+        selectors: &[Selector],
+        out: &mut Vec<Node<'a>>,
+    ) {
+        for selector in selectors {
+            self.select(selector, path, value, out);
+        }
+        for (child_path, child) in children(path, value) {
+            self.descend(&child_path, child, selectors, out);
+        }
+    }
+
+    fn select(
+        &self,
+        selector: &Selector,
+        path: &Path,
+        value: &'a JSONValue,
+        out: &mut Vec<Node<'a>>,
+    ) {
+        match (selector, value) {
+            (Selector::Name(name), JSONValue::Object(obj)) => {
+                if let Some(child) = obj.get(name) {
+                    out.push((path.join(PathSegment::Key(name.clone())), child));
+                }
+            }
+            (Selector::Wildcard, _) => out.extend(children(path, value)),
+            (Selector::Index(index), JSONValue::Array(arr)) => {
+                let len = arr.len() as i64;
+                let index = if *index < 0 { len + index } else { *index };
+                if (0..len).contains(&index) {
+                    let index = index as usize;
+                    out.push((path.join(index), &arr[index]));
+                }
+            }
+            (Selector::Slice { start, end, step }, JSONValue::Array(arr)) => {
+                for index in slice_indices(arr.len() as i64, *start, *end, step.unwrap_or(1)) {
+                    out.push((path.join(index), &arr[index]));
+                }
+            }
+            (Selector::Filter(expr), _) => {
+                for (child_path, child) in children(path, value) {
+                    if self.test(expr, child) {
+                        out.push((child_path, child));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn test(&self, expr: &Expr, current: &'a JSONValue) -> bool {
+        match expr {
+            Expr::Or(operands) => operands.iter().any(|e| self.test(e, current)),
+            Expr::And(operands) => operands.iter().all(|e| self.test(e, current)),
+            Expr::Not(operand) => !self.test(operand, current),
+            Expr::Comparison(left, op, right) => {
+                let left = self.comparable(left, current);
+                let right = self.comparable(right, current);
+                compare(&left, *op, &right)
+            }
+            Expr::Exists(query) => !self.query(query, current).is_empty(),
+            Expr::Function(call) => self.logical_call(call, current),
+        }
+    }
+
+    fn comparable(&self, comparable: &Comparable, current: &'a JSONValue) -> Operand<'a> {
+        match comparable {
+            Comparable::Literal(value) => Some(Cow::Owned(value.clone())),
+            Comparable::Query(query) => self.singular(query, current),
+            Comparable::Function(call) => self.value_call(call, current),
+        }
+    }
+
+    fn singular(&self, query: &Query, current: &'a JSONValue) -> Operand<'a> {
+        match self.query(query, current).as_slice() {
+            [(_, value)] => Some(Cow::Borrowed(*value)),
+            _ => None,
+        }
+    }
+
+    fn value_argument(&self, arg: &Argument, current: &'a JSONValue) -> Operand<'a> {
+        match arg {
+            Argument::Literal(value) => Some(Cow::Owned(value.clone())),
+            Argument::Query(query) => self.singular(query, current),
+            Argument::Function(call) => self.value_call(call, current),
+        }
+    }
+
+    fn nodes_argument(&self, arg: &Argument, current: &'a JSONValue) -> Vec<Node<'a>> {
+        match arg {
+            Argument::Query(query) => self.query(query, current),
+            // The parser only accepts queries for parameters of the nodes type.
+            _ => Vec::new(),
+        }
+    }
+
+    fn value_call(&self, call: &Call, current: &'a JSONValue) -> Operand<'a> {
+        let arg = &call.args[0];
+        match call.function {
+            Function::Length => {
+                let len = match self.value_argument(arg, current)?.as_ref() {
+                    JSONValue::String(s) => s.chars().count(),
+                    JSONValue::Utf16String(s) => s.to_string_lossy().chars().count(),
+                    JSONValue::Array(arr) => arr.len(),
+                    JSONValue::Object(obj) => obj.len(),
+                    _ => return None,
+                };
+                Some(Cow::Owned(JSONValue::Number(SignedNum64::Integer(
+                    len as i64,
+                ))))
+            }
+            Function::Count => {
+                let count = self.nodes_argument(arg, current).len();
+                Some(Cow::Owned(JSONValue::Number(SignedNum64::Integer(
+                    count as i64,
+                ))))
+            }
+            Function::Value => match self.nodes_argument(arg, current).as_slice() {
+                [(_, value)] => Some(Cow::Borrowed(*value)),
+                _ => None,
+            },
+            Function::Match | Function::Search => None,
+        }
+    }
+
+    fn logical_call(&self, call: &Call, current: &'a JSONValue) -> bool {
+        let anchored = match call.function {
+            Function::Match => true,
+            Function::Search => false,
+            _ => return false,
+        };
+        let text = self.value_argument(&call.args[0], current);
+        let pattern = self.value_argument(&call.args[1], current);
+        let (Some(JSONValue::String(text)), Some(JSONValue::String(pattern))) =
+            (text.as_deref(), pattern.as_deref())
+        else {
+            return false;
+        };
+
+        let mut regexes = self.regexes.borrow_mut();
+        let regex = regexes
+            .entry((pattern.clone(), anchored))
+            .or_insert_with(|| compile_iregexp(pattern, anchored));
+        regex.as_ref().is_some_and(|regex| regex.is_match(text))
+    }
+}
+
+fn children<'a>(path: &Path, value: &'a JSONValue) -> Vec<Node<'a>> {
+    match value {
+        JSONValue::Object(obj) => obj
+            .iter()
+            .map(|(key, child)| (path.join(PathSegment::Key(key.clone())), child))
+            .collect(),
+        JSONValue::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .map(|(i, child)| (path.join(i), child))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The indices selected by a slice, following RFC 9535 section 2.3.4.2.2.
+fn slice_indices(len: i64, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<usize> {
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+    match step.cmp(&0) {
+        Ordering::Equal => {}
+        Ordering::Greater => {
+            let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+            let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+            let mut i = lower;
+            while i < upper {
+                indices.push(i as usize);
+                i += step;
+            }
+        }
+        Ordering::Less => {
+            let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+            let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+            let mut i = upper;
+            while lower < i {
+                indices.push(i as usize);
+                i += step;
+            }
+        }
+    }
+    indices
+}
+
+/// Translates an RFC 9485 I-Regexp into the syntax of the `regex` crate, where `.` would
+/// otherwise match `\r`. Returns `None` if the pattern is invalid.
+fn compile_iregexp(pattern: &str, anchored: bool) -> Option<Regex> {
+    let mut translated = String::from(if anchored { "^(?:" } else { "(?:" });
+    let mut in_class = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                translated.push(c);
+                translated.push(chars.next()?);
+            }
+            '[' => {
+                in_class = true;
+                translated.push(c);
+            }
+            ']' => {
+                in_class = false;
+                translated.push(c);
+            }
+            '.' if !in_class => translated.push_str("[^\\n\\r]"),
+            c => translated.push(c),
+        }
+    }
+    translated.push_str(if anchored { ")$" } else { ")" });
+    Regex::new(&translated).ok()
+}
+
+fn compare(left: &Operand, op: CmpOp, right: &Operand) -> bool {
+    match op {
+        CmpOp::Eq => equal(left, right),
+        CmpOp::Ne => !equal(left, right),
+        CmpOp::Lt => less(left, right),
+        CmpOp::Le => less(left, right) || equal(left, right),
+        CmpOp::Gt => less(right, left),
+        CmpOp::Ge => less(right, left) || equal(left, right),
+    }
+}
+
+fn equal(left: &Operand, right: &Operand) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(left), Some(right)) => ordering::compare(left, right).is_eq(),
+        _ => false,
+    }
+}
+
+/// Only numbers and strings are ordered; comparing anything else with `<` is false.
+fn less(left: &Operand, right: &Operand) -> bool {
+    let (Some(left), Some(right)) = (left, right) else {
+        return false;
+    };
+    let ordered = matches!(
+        (left.as_ref(), right.as_ref()),
+        (JSONValue::Number(_), JSONValue::Number(_))
+            | (
+                JSONValue::String(_) | JSONValue::Utf16String(_),
+                JSONValue::String(_) | JSONValue::Utf16String(_)
+            )
+    );
+    ordered && ordering::compare(left, right).is_lt()
+}
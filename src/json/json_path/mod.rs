@@ -0,0 +1,294 @@
+mod ast;
+mod eval;
+mod parser;
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::json_value::JSONValue;
+use super::path::Path;
+use ast::Query;
+use eval::Evaluator;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum JSONPathErrorKind {
+    UnexpectedCharacter,
+    UnexpectedEnd,
+    InvalidEscape,
+    /// A malformed number, or an index outside of ±(2^53 - 1).
+    InvalidNumber,
+    UnknownFunction(String),
+    /// The function was called with too few or too many arguments.
+    ArgumentCount(String),
+    /// An argument of the function does not have the declared type, such as a query that can
+    /// select several nodes where a single value is expected.
+    ArgumentType(String),
+    /// An operand of a comparison is not a literal, a singular query or a function returning a
+    /// value.
+    NotComparable,
+    /// A filter expression is a literal or a function returning a value, without a comparison.
+    NotTestable,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct JSONPathError {
+    kind: JSONPathErrorKind,
+    position: usize,
+}
+
+impl JSONPathError {
+    pub fn kind(&self) -> &JSONPathErrorKind {
+        &self.kind
+    }
+
+    /// The byte offset into the query text.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for JSONPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} at position {}", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for JSONPathError {}
+
+/// A compiled RFC 9535 JSONPath query such as `$.users[?@.age >= 18].name`.
+///
+/// Supports name, wildcard, index, slice and filter selectors in child and descendant segments,
+/// and the functions `length`, `count`, `match`, `search` and `value`. Regular expressions use
+/// the I-Regexp syntax; an invalid pattern matches nothing.
+///
+/// ```
+/// use rust_json::json;
+/// use rust_json::json::json_path::JSONPath;
+///
+/// let value = json!({"users": [{"name": "Alice", "age": 15}, {"name": "Bob", "age": 42}]});
+/// let query = JSONPath::parse("$.users[?@.age >= 18].name").unwrap();
+/// let nodes = query.query(&value);
+/// assert_eq!(1, nodes.len());
+/// assert_eq!("$['users'][1]['name']", nodes[0].0.to_normalized());
+/// assert_eq!(&json!("Bob"), nodes[0].1);
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct JSONPath(Query);
+
+impl JSONPath {
+    pub fn parse(text: &str) -> Result<Self, JSONPathError> {
+        parser::Parser::parse(text).map(Self)
+    }
+
+    /// Returns the selected nodes with their paths, in the order defined by the query. A node
+    /// may appear more than once, e.g. for the union `$[0, 0]`.
+    pub fn query<'a>(&self, value: &'a JSONValue) -> Vec<(Path, &'a JSONValue)> {
+        Evaluator::new(value).query(&self.0, value)
+    }
+}
+
+impl FromStr for JSONPath {
+    type Err = JSONPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl JSONValue {
+    /// Parses `query` as a JSONPath and applies it to this value; see `JSONPath`.
+    pub fn select(&self, query: &str) -> Result<Vec<(Path, &JSONValue)>, JSONPathError> {
+        Ok(JSONPath::parse(query)?.query(self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    fn store() -> JSONValue {
+        json!({
+            "store": {
+                "book": [
+                    {"category": "reference", "author": "Nigel Rees", "price": 8.95},
+                    {"category": "fiction", "author": "Evelyn Waugh", "price": 12.99},
+                    {"category": "fiction", "author": "Herman Melville", "price": 8.99,
+                     "isbn": "0-553-21311-3"},
+                    {"category": "fiction", "author": "J. R. R. Tolkien", "price": 22.99,
+                     "isbn": "0-395-19395-8"}
+                ],
+                "bicycle": {"color": "red", "price": 399}
+            }
+        })
+    }
+
+    fn paths(value: &JSONValue, query: &str) -> Vec<String> {
+        value
+            .select(query)
+            .unwrap()
+            .iter()
+            .map(|(path, _)| path.to_normalized())
+            .collect()
+    }
+
+    fn values(value: &JSONValue, query: &str) -> Vec<JSONValue> {
+        value
+            .select(query)
+            .unwrap()
+            .into_iter()
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    #[test]
+    fn segments() {
+        let value = store();
+        assert_eq!(
+            vec![json!("Nigel Rees"), json!("Evelyn Waugh")],
+            values(&value, "$.store.book[:2].author")
+        );
+        assert_eq!(
+            vec![
+                json!(8.95),
+                json!(12.99),
+                json!(8.99),
+                json!(22.99),
+                json!(399)
+            ],
+            values(&value, "$..price")
+        );
+        assert_eq!(
+            vec!["$['store']['book'][3]", "$['store']['book'][2]"],
+            paths(&value, "$.store.book[-1, 2]")
+        );
+        assert_eq!(
+            vec![
+                "$['store']['bicycle']['color']",
+                "$['store']['bicycle']['price']"
+            ],
+            paths(&value, "$['store'][\"bicycle\"].*")
+        );
+        assert_eq!(4, value.select("$..book.*").unwrap().len());
+        assert_eq!(
+            vec![json!(399)],
+            values(&value, "$ .store ..bicycle[ 'price' ]")
+        );
+    }
+
+    #[test]
+    fn slices() {
+        let value = json!([0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(vec![json!(1), json!(3)], values(&value, "$[1:5:2]"));
+        assert_eq!(vec![json!(5), json!(3)], values(&value, "$[5:1:-2]"));
+        assert_eq!(vec![json!(6), json!(5)], values(&value, "$[-1:-3:-1]"));
+        assert_eq!(7, values(&value, "$[::-1]").len());
+        assert!(values(&value, "$[::0]").is_empty());
+        assert!(values(&value, "$[10:]").is_empty());
+    }
+
+    #[test]
+    fn filters() {
+        let value = store();
+        assert_eq!(
+            vec![json!("Herman Melville"), json!("J. R. R. Tolkien")],
+            values(&value, "$.store.book[?@.isbn].author")
+        );
+        assert_eq!(
+            vec![json!("Nigel Rees"), json!("Herman Melville")],
+            values(&value, "$..book[?@.price < 10].author")
+        );
+        assert_eq!(
+            vec![json!("Evelyn Waugh")],
+            values(
+                &value,
+                "$..book[?@.category == 'fiction' && !(@.isbn)].author"
+            )
+        );
+        assert_eq!(
+            vec![json!("Nigel Rees"), json!("J. R. R. Tolkien")],
+            values(
+                &value,
+                "$..book[?@.price > 20 || @.category != \"fiction\"].author"
+            )
+        );
+        assert_eq!(
+            vec![json!("red")],
+            values(&value, "$.store[?@.price == $.store.bicycle.price].color")
+        );
+        assert_eq!(
+            vec![json!(1), json!(1.0)],
+            values(&json!([1, 1.0, "1", null, [1]]), "$[?@ == 1]")
+        );
+        assert_eq!(
+            vec![json!({"a": [1.0]})],
+            values(
+                &json!([{"a": [1.0]}, {"a": [2]}]),
+                "$[?@.a == $[0].a && @.a[0] <= 1]"
+            )
+        );
+        assert_eq!(
+            vec![json!(null), json!({})],
+            values(&json!([null, {}]), "$[?@.missing == $.absent]")
+        );
+    }
+
+    #[test]
+    fn functions() {
+        let value = store();
+        assert_eq!(
+            vec![json!("J. R. R. Tolkien")],
+            values(&value, "$..book[?length(@.author) > 15].author")
+        );
+        assert_eq!(
+            vec![json!("Herman Melville")],
+            values(&value, "$..book[?match(@.author, 'H.*')].author")
+        );
+        assert_eq!(
+            vec![json!("Herman Melville"), json!("J. R. R. Tolkien")],
+            values(&value, "$..book[?search(@.isbn, '-3$|-8$')].author")
+        );
+        assert_eq!(
+            vec![json!({"color": "red", "price": 399})],
+            values(&value, "$.store[?count(@.*) == 2]")
+        );
+        assert_eq!(
+            vec![json!("Nigel Rees")],
+            values(&value, "$..book[?value(@..category) == 'reference'].author")
+        );
+        assert!(values(&json!(["a\nb"]), "$[?match(@, 'a.b')]").is_empty());
+        assert!(values(&json!(["a"]), "$[?match(@, '(')]").is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let kind = |query: &str| JSONPath::parse(query).unwrap_err().kind().clone();
+        assert_eq!(JSONPathErrorKind::UnexpectedCharacter, kind("a"));
+        assert_eq!(JSONPathErrorKind::UnexpectedCharacter, kind("$ "));
+        assert_eq!(JSONPathErrorKind::UnexpectedEnd, kind("$['a'"));
+        assert_eq!(JSONPathErrorKind::InvalidEscape, kind("$['\\x']"));
+        assert_eq!(JSONPathErrorKind::InvalidNumber, kind("$[01]"));
+        assert_eq!(
+            JSONPathErrorKind::InvalidNumber,
+            kind("$[9007199254740992]")
+        );
+        assert_eq!(
+            JSONPathErrorKind::UnknownFunction("foo".into()),
+            kind("$[?foo(@)]")
+        );
+        assert_eq!(
+            JSONPathErrorKind::ArgumentCount("length".into()),
+            kind("$[?length(@, @) == 1]")
+        );
+        assert_eq!(
+            JSONPathErrorKind::ArgumentType("length".into()),
+            kind("$[?length(@.*) == 1]")
+        );
+        assert_eq!(JSONPathErrorKind::NotComparable, kind("$[?@.* == 1]"));
+        assert_eq!(JSONPathErrorKind::NotTestable, kind("$[?length(@)]"));
+        assert_eq!(JSONPathErrorKind::NotTestable, kind("$[?1]"));
+
+        let error = JSONPath::parse("$.a[?@.b == ]").unwrap_err();
+        assert_eq!("UnexpectedCharacter at position 12", error.to_string());
+    }
+}
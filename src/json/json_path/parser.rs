@@ -0,0 +1,533 @@
+use super::super::json_value::JSONValue;
+use super::super::util::signed_num_64::SignedNum64;
+use super::ast::{
+    Argument, Call, CmpOp, Comparable, Expr, Function, Query, Segment, Selector, Type,
+};
+use super::{JSONPathError, JSONPathErrorKind};
+
+/// The largest magnitude of an index or slice bound: integers outside of it are not
+/// interoperable according to I-JSON.
+const MAX_INT: i64 = (1 << 53) - 1;
+
+/// A parsed operand before it is known whether it is compared or tested.
+enum Primary {
+    Literal(JSONValue),
+    Query(Query),
+    Function(Call),
+}
+
+pub(super) struct Parser<'s> {
+    text: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    pub fn parse(text: &'s str) -> Result<Query, JSONPathError> {
+        let mut parser = Parser { text, pos: 0 };
+        parser.expect('$')?;
+        let query = Query {
+            absolute: true,
+            segments: parser.segments()?,
+        };
+        match parser.peek() {
+            None => Ok(query),
+            Some(_) => Err(parser.error(JSONPathErrorKind::UnexpectedCharacter)),
+        }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JSONPathError> {
+        match self.peek() {
+            Some(next) if next == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, kind: JSONPathErrorKind) -> JSONPathError {
+        JSONPathError {
+            kind,
+            position: self.pos,
+        }
+    }
+
+    fn error_at(&self, kind: JSONPathErrorKind, position: usize) -> JSONPathError {
+        JSONPathError { kind, position }
+    }
+
+    fn unexpected(&self) -> JSONPathError {
+        match self.peek() {
+            Some(_) => self.error(JSONPathErrorKind::UnexpectedCharacter),
+            None => self.error(JSONPathErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, JSONPathError> {
+        let mut segments = Vec::new();
+        loop {
+            let start = self.pos;
+            self.skip_whitespace();
+            if self.eat("..") {
+                let selectors = match self.peek() {
+                    Some('[') => self.bracketed()?,
+                    Some('*') => {
+                        self.bump();
+                        vec![Selector::Wildcard]
+                    }
+                    _ => vec![self.member_name()?],
+                };
+                segments.push(Segment {
+                    descendant: true,
+                    selectors,
+                });
+            } else if self.eat(".") {
+                let selector = match self.peek() {
+                    Some('*') => {
+                        self.bump();
+                        Selector::Wildcard
+                    }
+                    _ => self.member_name()?,
+                };
+                segments.push(Segment {
+                    descendant: false,
+                    selectors: vec![selector],
+                });
+            } else if self.peek() == Some('[') {
+                segments.push(Segment {
+                    descendant: false,
+                    selectors: self.bracketed()?,
+                });
+            } else {
+                self.pos = start;
+                return Ok(segments);
+            }
+        }
+    }
+
+    /// Parses the name of the `.name` shorthand.
+    fn member_name(&mut self) -> Result<Selector, JSONPathError> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || !c.is_ascii() => {}
+            _ => return Err(self.unexpected()),
+        }
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()) {
+                break;
+            }
+            self.bump();
+        }
+        Ok(Selector::Name(self.text[start..self.pos].into()))
+    }
+
+    fn bracketed(&mut self) -> Result<Vec<Selector>, JSONPathError> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(']')?;
+        Ok(selectors)
+    }
+
+    fn selector(&mut self) -> Result<Selector, JSONPathError> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.string()?.into())),
+            Some('*') => {
+                self.bump();
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.bump();
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.logical_or()?))
+            }
+            _ => self.index_or_slice(),
+        }
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, JSONPathError> {
+        let start = self.optional_int()?;
+        self.skip_whitespace();
+        if !self.eat(":") {
+            return start.map(Selector::Index).ok_or_else(|| self.unexpected());
+        }
+        self.skip_whitespace();
+        let end = self.optional_int()?;
+        self.skip_whitespace();
+        let step = if self.eat(":") {
+            self.skip_whitespace();
+            self.optional_int()?
+        } else {
+            None
+        };
+        Ok(Selector::Slice { start, end, step })
+    }
+
+    fn optional_int(&mut self) -> Result<Option<i64>, JSONPathError> {
+        match self.peek() {
+            Some('-' | '0'..='9') => self.int().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn int(&mut self) -> Result<i64, JSONPathError> {
+        let start = self.pos;
+        let negative = self.eat("-");
+        let digits_start = self.pos;
+        while let Some('0'..='9') = self.peek() {
+            self.pos += 1;
+        }
+        let digits = &self.text[digits_start..self.pos];
+        let malformed =
+            digits.is_empty() || (digits.starts_with('0') && (digits.len() > 1 || negative));
+        match digits.parse::<i64>() {
+            Ok(value) if !malformed && value <= MAX_INT => {
+                Ok(if negative { -value } else { value })
+            }
+            _ => Err(self.error_at(JSONPathErrorKind::InvalidNumber, start)),
+        }
+    }
+
+    /// Parses a single- or double-quoted string literal.
+    fn string(&mut self) -> Result<String, JSONPathError> {
+        let quote = self.bump().ok_or_else(|| self.unexpected())?;
+        let mut result = String::new();
+        loop {
+            let position = self.pos;
+            match self.bump() {
+                None => return Err(self.error(JSONPathErrorKind::UnexpectedEnd)),
+                Some(c) if c == quote => return Ok(result),
+                Some('\\') => {
+                    let c = match self.bump() {
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(c @ ('/' | '\\')) => c,
+                        Some(c) if c == quote => c,
+                        Some('u') => self.unicode_escape().ok_or_else(|| {
+                            self.error_at(JSONPathErrorKind::InvalidEscape, position)
+                        })?,
+                        _ => return Err(self.error_at(JSONPathErrorKind::InvalidEscape, position)),
+                    };
+                    result.push(c);
+                }
+                Some(c) if c < '\x20' => {
+                    return Err(self.error_at(JSONPathErrorKind::UnexpectedCharacter, position))
+                }
+                Some(c) => result.push(c),
+            }
+        }
+    }
+
+    /// Parses the digits of a `\u` escape, including the low half of a surrogate pair.
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+        if !self.eat("\\u") {
+            return None;
+        }
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.rest().get(..4)?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    fn logical_or(&mut self) -> Result<Expr, JSONPathError> {
+        let mut operands = vec![self.logical_and()?];
+        loop {
+            let start = self.pos;
+            self.skip_whitespace();
+            if !self.eat("||") {
+                self.pos = start;
+                break;
+            }
+            self.skip_whitespace();
+            operands.push(self.logical_and()?);
+        }
+        Ok(match operands.len() {
+            1 => operands.pop().unwrap(),
+            _ => Expr::Or(operands),
+        })
+    }
+
+    fn logical_and(&mut self) -> Result<Expr, JSONPathError> {
+        let mut operands = vec![self.basic()?];
+        loop {
+            let start = self.pos;
+            self.skip_whitespace();
+            if !self.eat("&&") {
+                self.pos = start;
+                break;
+            }
+            self.skip_whitespace();
+            operands.push(self.basic()?);
+        }
+        Ok(match operands.len() {
+            1 => operands.pop().unwrap(),
+            _ => Expr::And(operands),
+        })
+    }
+
+    fn basic(&mut self) -> Result<Expr, JSONPathError> {
+        if self.eat("!") {
+            self.skip_whitespace();
+            let operand = if self.peek() == Some('(') {
+                self.parenthesized()?
+            } else {
+                let start = self.pos;
+                let primary = self.primary()?;
+                self.test(primary, start)?
+            };
+            return Ok(Expr::Not(Box::new(operand)));
+        }
+        if self.peek() == Some('(') {
+            return self.parenthesized();
+        }
+
+        let start = self.pos;
+        let left = self.primary()?;
+        let before_op = self.pos;
+        self.skip_whitespace();
+        let Some(op) = self.comparison_op() else {
+            self.pos = before_op;
+            return self.test(left, start);
+        };
+        self.skip_whitespace();
+        let right_start = self.pos;
+        let right = self.primary()?;
+        Ok(Expr::Comparison(
+            self.comparable(left, start)?,
+            op,
+            self.comparable(right, right_start)?,
+        ))
+    }
+
+    fn parenthesized(&mut self) -> Result<Expr, JSONPathError> {
+        self.expect('(')?;
+        self.skip_whitespace();
+        let expr = self.logical_or()?;
+        self.skip_whitespace();
+        self.expect(')')?;
+        Ok(expr)
+    }
+
+    fn comparison_op(&mut self) -> Option<CmpOp> {
+        // Two-character operators first, so that `<=` is not read as `<`.
+        let ops = [
+            ("==", CmpOp::Eq),
+            ("!=", CmpOp::Ne),
+            ("<=", CmpOp::Le),
+            (">=", CmpOp::Ge),
+            ("<", CmpOp::Lt),
+            (">", CmpOp::Gt),
+        ];
+        ops.into_iter()
+            .find(|(text, _)| self.eat(text))
+            .map(|(_, op)| op)
+    }
+
+    fn test(&self, primary: Primary, start: usize) -> Result<Expr, JSONPathError> {
+        match primary {
+            Primary::Query(query) => Ok(Expr::Exists(query)),
+            Primary::Function(call) if call.function.result() == Type::Logical => {
+                Ok(Expr::Function(call))
+            }
+            _ => Err(self.error_at(JSONPathErrorKind::NotTestable, start)),
+        }
+    }
+
+    fn comparable(&self, primary: Primary, start: usize) -> Result<Comparable, JSONPathError> {
+        match primary {
+            Primary::Literal(value) => Ok(Comparable::Literal(value)),
+            Primary::Query(query) if query.is_singular() => Ok(Comparable::Query(query)),
+            Primary::Function(call) if call.function.result() == Type::Value => {
+                Ok(Comparable::Function(call))
+            }
+            _ => Err(self.error_at(JSONPathErrorKind::NotComparable, start)),
+        }
+    }
+
+    /// Parses a literal, a filter query or a function call.
+    fn primary(&mut self) -> Result<Primary, JSONPathError> {
+        match self.peek() {
+            Some('@' | '$') => Ok(Primary::Query(self.filter_query()?)),
+            Some('\'' | '"') => Ok(Primary::Literal(JSONValue::String(self.string()?))),
+            Some('-' | '0'..='9') => Ok(Primary::Literal(self.number()?)),
+            Some('a'..='z') => {
+                let start = self.pos;
+                while let Some('a'..='z' | '0'..='9' | '_') = self.peek() {
+                    self.pos += 1;
+                }
+                let name = &self.text[start..self.pos];
+                if self.peek() == Some('(') {
+                    return Ok(Primary::Function(self.call(name, start)?));
+                }
+                match name {
+                    "true" => Ok(Primary::Literal(JSONValue::True)),
+                    "false" => Ok(Primary::Literal(JSONValue::False)),
+                    "null" => Ok(Primary::Literal(JSONValue::Null)),
+                    _ => Err(self.error_at(JSONPathErrorKind::UnexpectedCharacter, start)),
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn filter_query(&mut self) -> Result<Query, JSONPathError> {
+        let absolute = self.bump() == Some('$');
+        Ok(Query {
+            absolute,
+            segments: self.segments()?,
+        })
+    }
+
+    fn number(&mut self) -> Result<JSONValue, JSONPathError> {
+        let start = self.pos;
+        self.eat("-");
+        let int_start = self.pos;
+        while let Some('0'..='9') = self.peek() {
+            self.pos += 1;
+        }
+        let int = &self.text[int_start..self.pos];
+        if int.is_empty() || (int.starts_with('0') && int.len() > 1) {
+            return Err(self.error_at(JSONPathErrorKind::InvalidNumber, start));
+        }
+
+        let mut is_float = false;
+        if self.eat(".") {
+            is_float = true;
+            if !self.digits() {
+                return Err(self.error_at(JSONPathErrorKind::InvalidNumber, start));
+            }
+        }
+        if self.eat("e") || self.eat("E") {
+            is_float = true;
+            let _ = self.eat("+") || self.eat("-");
+            if !self.digits() {
+                return Err(self.error_at(JSONPathErrorKind::InvalidNumber, start));
+            }
+        }
+
+        let text = &self.text[start..self.pos];
+        let num = match text.parse::<i64>() {
+            Ok(num) if !is_float => SignedNum64::Integer(num),
+            _ => SignedNum64::Float(
+                text.parse()
+                    .map_err(|_| self.error_at(JSONPathErrorKind::InvalidNumber, start))?,
+            ),
+        };
+        Ok(JSONValue::Number(num))
+    }
+
+    fn digits(&mut self) -> bool {
+        let start = self.pos;
+        while let Some('0'..='9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn call(&mut self, name: &str, start: usize) -> Result<Call, JSONPathError> {
+        let function = Function::from_name(name)
+            .ok_or_else(|| self.error_at(JSONPathErrorKind::UnknownFunction(name.into()), start))?;
+        self.expect('(')?;
+        self.skip_whitespace();
+
+        let mut args = Vec::new();
+        if self.peek() != Some(')') {
+            loop {
+                let arg_start = self.pos;
+                let arg = self.argument(function, args.len(), arg_start, name)?;
+                args.push(arg);
+                self.skip_whitespace();
+                if !self.eat(",") {
+                    break;
+                }
+                self.skip_whitespace();
+            }
+        }
+        self.expect(')')?;
+
+        if args.len() != function.parameters().len() {
+            return Err(self.error_at(JSONPathErrorKind::ArgumentCount(name.into()), start));
+        }
+        Ok(Call { function, args })
+    }
+
+    /// Parses an argument and checks it against the declared parameter type.
+    fn argument(
+        &mut self,
+        function: Function,
+        index: usize,
+        start: usize,
+        name: &str,
+    ) -> Result<Argument, JSONPathError> {
+        let parameter = function.parameters().get(index).copied();
+        let primary = self.primary()?;
+        let arg = match (parameter, primary) {
+            (None, _) => {
+                return Err(self.error_at(JSONPathErrorKind::ArgumentCount(name.into()), start))
+            }
+            (Some(Type::Value), Primary::Literal(value)) => Argument::Literal(value),
+            (Some(Type::Value), Primary::Query(query)) if query.is_singular() => {
+                Argument::Query(query)
+            }
+            (Some(Type::Nodes), Primary::Query(query)) => Argument::Query(query),
+            (Some(parameter), Primary::Function(call)) if call.function.result() == parameter => {
+                Argument::Function(call)
+            }
+            _ => return Err(self.error_at(JSONPathErrorKind::ArgumentType(name.into()), start)),
+        };
+        Ok(arg)
+    }
+}
@@ -1,19 +1,40 @@
-use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
 
+use super::object::JSONObject;
+use super::utf16_string::Utf16String;
 use super::util::signed_num_64::SignedNum64;
 
+/// An object key. Keys are reference counted so that equal keys can share one allocation; see
+/// `ParserOptions::intern_keys`.
+pub type Key = Arc<str>;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum JSONValue {
     True,
     False,
     Null,
-    Object(HashMap<String, JSONValue>),
+    Object(JSONObject),
     Array(Vec<JSONValue>),
     Number(SignedNum64),
     String(String),
+    /// A string holding unpaired surrogates; see `LoneSurrogates::Preserve`.
+    Utf16String(Utf16String),
 }
 
 impl JSONValue {
+    /// Returns the name of the value's JSON type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JSONValue::True | JSONValue::False => "boolean",
+            JSONValue::Null => "null",
+            JSONValue::Object(_) => "object",
+            JSONValue::Array(_) => "array",
+            JSONValue::Number(_) => "number",
+            JSONValue::String(_) | JSONValue::Utf16String(_) => "string",
+        }
+    }
+
     pub fn as_bool(&self) -> Option<bool> {
         match self {
             JSONValue::True => Some(true),
@@ -28,7 +49,7 @@ impl JSONValue {
 
     pub fn as_number(&self) -> Option<SignedNum64> {
         match self {
-            JSONValue::Number(num) => Some(num.to_owned()),
+            JSONValue::Number(num) => Some(*num),
             _ => None,
         }
     }
@@ -49,40 +70,314 @@ impl JSONValue {
         }
     }
 
+    /// Returns the string value. Unpaired surrogates in a `Utf16String` are replaced with
+    /// U+FFFD.
     pub fn as_string(&self) -> Option<String> {
         match self {
             JSONValue::String(val) => Some(val.to_owned()),
+            JSONValue::Utf16String(val) => Some(val.to_string_lossy()),
             _ => None,
         }
     }
 
-    pub fn is_array(&self) -> bool {
+    /// Borrows the string value. Returns `None` for a `Utf16String`, which has no `str` form.
+    pub fn as_str(&self) -> Option<&str> {
         match self {
-            JSONValue::Array(_) => true,
-            _ => false,
+            JSONValue::String(val) => Some(val),
+            _ => None,
         }
     }
 
-    pub fn get_as_array(&self, index: usize) -> Option<&JSONValue> {
-        (match self {
+    pub fn as_utf16_string(&self) -> Option<Utf16String> {
+        match self {
+            JSONValue::String(val) => Some(Utf16String::from(val.as_str())),
+            JSONValue::Utf16String(val) => Some(val.to_owned()),
+            _ => None,
+        }
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, JSONValue::Array(_))
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JSONValue>> {
+        match self {
             JSONValue::Array(arr) => Some(arr),
             _ => None,
-        })
-        .and_then(|arr| arr.get(index))
+        }
+    }
+
+    pub fn get_as_array(&self, index: usize) -> Option<&JSONValue> {
+        self.as_array().and_then(|arr| arr.get(index))
+    }
+
+    /// Iterates over the array elements; yields nothing if the value is not an array.
+    pub fn iter_array(&self) -> impl Iterator<Item = &JSONValue> {
+        self.as_array().into_iter().flatten()
     }
 
     pub fn is_object(&self) -> bool {
+        matches!(self, JSONValue::Object(_))
+    }
+
+    pub fn as_object(&self) -> Option<&JSONObject> {
         match self {
-            JSONValue::Object(_) => true,
-            _ => false,
+            JSONValue::Object(obj) => Some(obj),
+            _ => None,
         }
     }
 
     pub fn get_as_object(&self, key: &str) -> Option<&JSONValue> {
-        (match self {
+        self.as_object().and_then(|obj| obj.get(key))
+    }
+
+    /// Iterates over the object members in order; yields nothing if the value is not an object.
+    pub fn iter_object(&self) -> impl Iterator<Item = (&Key, &JSONValue)> {
+        self.as_object().into_iter().flatten()
+    }
+
+    /// Iterates over the object keys in order; yields nothing if the value is not an object.
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.iter_object().map(|(k, _)| k)
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JSONValue>> {
+        match self {
+            JSONValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut JSONObject> {
+        match self {
             JSONValue::Object(obj) => Some(obj),
             _ => None,
-        })
-        .and_then(|obj| obj.get(key))
+        }
+    }
+
+    pub fn get_as_array_mut(&mut self, index: usize) -> Option<&mut JSONValue> {
+        self.as_array_mut().and_then(|arr| arr.get_mut(index))
+    }
+
+    pub fn get_as_object_mut(&mut self, key: &str) -> Option<&mut JSONValue> {
+        self.as_object_mut().and_then(|obj| obj.get_mut(key))
+    }
+
+    /// Looks up an object member by key or an array element by index. Returns `None` if the
+    /// value is of the other kind or has no such member or element.
+    pub fn get(&self, index: impl ValueIndex) -> Option<&JSONValue> {
+        index.index_into(self)
+    }
+
+    /// The mutable counterpart of `get`.
+    pub fn get_mut(&mut self, index: impl ValueIndex) -> Option<&mut JSONValue> {
+        index.index_into_mut(self)
+    }
+
+    /// Inserts an object member, returning the previous value for the key if there was one.
+    /// `Null` is turned into an empty object first.
+    ///
+    /// Panics if the value is neither an object nor `Null`.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Key>,
+        value: impl Into<JSONValue>,
+    ) -> Option<JSONValue> {
+        if self.is_null() {
+            *self = JSONValue::Object(JSONObject::new());
+        }
+
+        match self {
+            JSONValue::Object(obj) => obj.insert(key, value.into()),
+            _ => panic!("cannot insert into {}", self.type_name()),
+        }
+    }
+
+    /// Removes an object member, preserving the order of the remaining members. Returns `None`
+    /// if the value is not an object or has no such key.
+    pub fn remove(&mut self, key: &str) -> Option<JSONValue> {
+        self.as_object_mut().and_then(|obj| obj.remove(key))
+    }
+
+    /// Removes an array element, shifting the following elements down. Returns `None` if the
+    /// value is not an array or the index is out of bounds.
+    pub fn remove_index(&mut self, index: usize) -> Option<JSONValue> {
+        self.as_array_mut()
+            .filter(|arr| index < arr.len())
+            .map(|arr| arr.remove(index))
+    }
+
+    /// Appends an array element. `Null` is turned into an empty array first.
+    ///
+    /// Panics if the value is neither an array nor `Null`.
+    pub fn push(&mut self, value: impl Into<JSONValue>) {
+        if self.is_null() {
+            *self = JSONValue::Array(Vec::new());
+        }
+
+        match self {
+            JSONValue::Array(arr) => arr.push(value.into()),
+            _ => panic!("cannot push into {}", self.type_name()),
+        }
+    }
+
+    /// Removes the last array element. Returns `None` if the value is not an array or is empty.
+    pub fn pop(&mut self) -> Option<JSONValue> {
+        self.as_array_mut().and_then(|arr| arr.pop())
+    }
+
+    /// Takes the value out, leaving `Null` in its place.
+    pub fn take(&mut self) -> JSONValue {
+        mem::replace(self, JSONValue::Null)
+    }
+
+    /// Keeps only the array elements, or the object members, whose value satisfies `f`. Other
+    /// values are left unchanged.
+    pub fn retain(&mut self, mut f: impl FnMut(&JSONValue) -> bool) {
+        match self {
+            JSONValue::Array(arr) => arr.retain(|v| f(v)),
+            JSONValue::Object(obj) => obj.retain(|_, v| f(v)),
+            _ => {}
+        }
+    }
+}
+
+/// A key or array index accepted by `JSONValue::get` and `JSONValue::get_mut`.
+pub trait ValueIndex {
+    fn index_into<'v>(&self, value: &'v JSONValue) -> Option<&'v JSONValue>;
+
+    fn index_into_mut<'v>(&self, value: &'v mut JSONValue) -> Option<&'v mut JSONValue>;
+}
+
+impl ValueIndex for usize {
+    fn index_into<'v>(&self, value: &'v JSONValue) -> Option<&'v JSONValue> {
+        value.get_as_array(*self)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut JSONValue) -> Option<&'v mut JSONValue> {
+        value.get_as_array_mut(*self)
+    }
+}
+
+impl ValueIndex for str {
+    fn index_into<'v>(&self, value: &'v JSONValue) -> Option<&'v JSONValue> {
+        value.get_as_object(self)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut JSONValue) -> Option<&'v mut JSONValue> {
+        value.get_as_object_mut(self)
+    }
+}
+
+impl ValueIndex for String {
+    fn index_into<'v>(&self, value: &'v JSONValue) -> Option<&'v JSONValue> {
+        value.get_as_object(self)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut JSONValue) -> Option<&'v mut JSONValue> {
+        value.get_as_object_mut(self)
+    }
+}
+
+impl<T: ValueIndex + ?Sized> ValueIndex for &T {
+    fn index_into<'v>(&self, value: &'v JSONValue) -> Option<&'v JSONValue> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut JSONValue) -> Option<&'v mut JSONValue> {
+        (**self).index_into_mut(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parser::Parser;
+    use super::*;
+
+    #[test]
+    fn borrow() {
+        let value = Parser::parse("{\"name\": \"Bob\", \"tags\": [\"a\", \"b\"]}").unwrap();
+
+        assert_eq!(Some("Bob"), value["name"].as_str());
+        assert_eq!(None, value["tags"].as_str());
+        assert_eq!(Some(2), value["tags"].as_array().map(|arr| arr.len()));
+        assert_eq!(Some(2), value.as_object().map(|obj| obj.len()));
+        assert_eq!(
+            vec!["a", "b"],
+            value["tags"]
+                .iter_array()
+                .filter_map(|v| v.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["name", "tags"],
+            value.keys().map(|k| &**k).collect::<Vec<_>>()
+        );
+        assert_eq!(2, value.iter_object().count());
+        assert_eq!(0, value["name"].iter_array().count());
+        assert_eq!(0, value["tags"].keys().count());
+    }
+
+    #[test]
+    fn mutate_object() {
+        let mut value = Parser::parse("{\"a\": 1, \"b\": {\"c\": 2}}").unwrap();
+
+        assert_eq!(Some(JSONValue::from(1)), value.insert("a", 10));
+        assert_eq!(None, value.insert("d", "x"));
+        *value.get_as_object_mut("b").unwrap() = JSONValue::Null;
+        assert_eq!(Some(JSONValue::Null), value.remove("b"));
+        assert_eq!(None, value.remove("b"));
+
+        assert_eq!(Parser::parse("{\"a\": 10, \"d\": \"x\"}"), Ok(value));
+    }
+
+    #[test]
+    fn mutate_array() {
+        let mut value = JSONValue::Null;
+        value.push(1);
+        value.push(2);
+        value.push(3);
+        value.push(4);
+
+        assert_eq!(Some(JSONValue::from(4)), value.pop());
+        assert_eq!(Some(JSONValue::from(1)), value.remove_index(0));
+        assert_eq!(None, value.remove_index(5));
+        *value.get_as_array_mut(1).unwrap() = JSONValue::from(30);
+        value.as_array_mut().unwrap().insert(0, JSONValue::True);
+
+        assert_eq!(Parser::parse("[true, 2, 30]"), Ok(value));
+    }
+
+    #[test]
+    fn get_by_key_or_index() {
+        let mut value = Parser::parse("{\"a\": [1, {\"b\": 2}]}").unwrap();
+
+        assert_eq!(
+            Some(&JSONValue::from(1)),
+            value.get("a").and_then(|a| a.get(0))
+        );
+        assert_eq!(None, value.get(0));
+        assert_eq!(None, value.get("a").and_then(|a| a.get(2)));
+        assert_eq!(None, value.get("missing".to_string()));
+
+        *value
+            .get_mut("a")
+            .and_then(|a| a.get_mut(1))
+            .and_then(|o| o.get_mut("b"))
+            .unwrap() = JSONValue::from(20);
+        assert_eq!(None, value.get_mut("a").and_then(|a| a.get_mut("b")));
+
+        assert_eq!(Parser::parse("{\"a\": [1, {\"b\": 20}]}"), Ok(value));
+    }
+
+    #[test]
+    fn take_and_retain() {
+        let mut value = Parser::parse("{\"list\": [1, null, 2, null]}").unwrap();
+
+        let mut list = value["list"].take();
+        list.retain(|v| !v.is_null());
+
+        assert_eq!(Parser::parse("[1, 2]"), Ok(list));
+        assert_eq!(Parser::parse("{\"list\": null}"), Ok(value));
     }
 }
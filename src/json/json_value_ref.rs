@@ -0,0 +1,177 @@
+use std::borrow::Cow;
+use std::ops::Index;
+
+use super::json_value::JSONValue;
+use super::object::JSONObject;
+use super::object_ref::JSONObjectRef;
+use super::utf16_string::Utf16String;
+use super::util::signed_num_64::SignedNum64;
+
+/// A JSON value whose strings and keys borrow from the parsed text where possible, produced by
+/// `Parser::parse_borrowed`. Only strings containing escape sequences are allocated.
+#[derive(Debug, PartialEq, Clone)]
+pub enum JSONValueRef<'a> {
+    True,
+    False,
+    Null,
+    Object(JSONObjectRef<'a>),
+    Array(Vec<JSONValueRef<'a>>),
+    Number(SignedNum64),
+    String(Cow<'a, str>),
+    /// A string holding unpaired surrogates; see `LoneSurrogates::Preserve`.
+    Utf16String(Utf16String),
+}
+
+static NULL: JSONValueRef<'static> = JSONValueRef::Null;
+
+impl<'a> JSONValueRef<'a> {
+    /// Returns the name of the value's JSON type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JSONValueRef::True | JSONValueRef::False => "boolean",
+            JSONValueRef::Null => "null",
+            JSONValueRef::Object(_) => "object",
+            JSONValueRef::Array(_) => "array",
+            JSONValueRef::Number(_) => "number",
+            JSONValueRef::String(_) | JSONValueRef::Utf16String(_) => "string",
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JSONValueRef::True => Some(true),
+            JSONValueRef::False => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == JSONValueRef::Null
+    }
+
+    pub fn as_number(&self) -> Option<SignedNum64> {
+        match self {
+            JSONValueRef::Number(num) => Some(*num),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JSONValueRef::Number(SignedNum64::Integer(num)) => Some(*num),
+            JSONValueRef::Number(SignedNum64::Float(num)) => Some(*num as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JSONValueRef::Number(SignedNum64::Integer(num)) => Some(*num as f64),
+            JSONValueRef::Number(SignedNum64::Float(num)) => Some(*num),
+            _ => None,
+        }
+    }
+
+    /// Borrows the string value. Returns `None` for a `Utf16String`, which has no `str` form.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JSONValueRef::String(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns the string value. Unpaired surrogates in a `Utf16String` are replaced with
+    /// U+FFFD.
+    pub fn as_string(&self) -> Option<String> {
+        match self {
+            JSONValueRef::String(val) => Some(val.to_string()),
+            JSONValueRef::Utf16String(val) => Some(val.to_string_lossy()),
+            _ => None,
+        }
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, JSONValueRef::Array(_))
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JSONValueRef<'a>>> {
+        match self {
+            JSONValueRef::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn get_as_array(&self, index: usize) -> Option<&JSONValueRef<'a>> {
+        self.as_array().and_then(|arr| arr.get(index))
+    }
+
+    /// Iterates over the array elements; yields nothing if the value is not an array.
+    pub fn iter_array(&self) -> impl Iterator<Item = &JSONValueRef<'a>> {
+        self.as_array().into_iter().flatten()
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, JSONValueRef::Object(_))
+    }
+
+    pub fn as_object(&self) -> Option<&JSONObjectRef<'a>> {
+        match self {
+            JSONValueRef::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn get_as_object(&self, key: &str) -> Option<&JSONValueRef<'a>> {
+        self.as_object().and_then(|obj| obj.get(key))
+    }
+
+    /// Iterates over the object members in order; yields nothing if the value is not an object.
+    pub fn iter_object(&self) -> impl Iterator<Item = (&Cow<'a, str>, &JSONValueRef<'a>)> {
+        self.as_object().into_iter().flat_map(|obj| obj.iter())
+    }
+
+    /// Iterates over the object keys in order; yields nothing if the value is not an object.
+    pub fn keys(&self) -> impl Iterator<Item = &Cow<'a, str>> {
+        self.iter_object().map(|(k, _)| k)
+    }
+
+    /// Copies the value into an owned `JSONValue`, releasing the borrow of the input text.
+    pub fn into_owned(self) -> JSONValue {
+        match self {
+            JSONValueRef::True => JSONValue::True,
+            JSONValueRef::False => JSONValue::False,
+            JSONValueRef::Null => JSONValue::Null,
+            JSONValueRef::Object(obj) => JSONValue::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (k, v.into_owned()))
+                    .collect::<JSONObject>(),
+            ),
+            JSONValueRef::Array(arr) => {
+                JSONValue::Array(arr.into_iter().map(JSONValueRef::into_owned).collect())
+            }
+            JSONValueRef::Number(num) => JSONValue::Number(num),
+            JSONValueRef::String(s) => JSONValue::String(s.into_owned()),
+            JSONValueRef::Utf16String(s) => JSONValue::Utf16String(s),
+        }
+    }
+}
+
+/// Returns the array element, or `Null` if the value is not an array or the index is out of
+/// bounds.
+impl<'a> Index<usize> for JSONValueRef<'a> {
+    type Output = JSONValueRef<'a>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get_as_array(index).unwrap_or(&NULL)
+    }
+}
+
+/// Returns the value of the object member, or `Null` if the value is not an object or the key
+/// is missing.
+impl<'a> Index<&str> for JSONValueRef<'a> {
+    type Output = JSONValueRef<'a>;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        self.get_as_object(key).unwrap_or(&NULL)
+    }
+}
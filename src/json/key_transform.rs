@@ -0,0 +1,115 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// A function applied to every object key as it is parsed.
+#[derive(Clone)]
+pub struct KeyTransform(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl KeyTransform {
+    pub fn new(f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Converts `camelCase` and `PascalCase` keys to `snake_case`.
+    pub fn snake_case() -> Self {
+        Self::new(to_snake_case)
+    }
+
+    /// Converts `snake_case` keys to `camelCase`.
+    pub fn camel_case() -> Self {
+        Self::new(to_camel_case)
+    }
+
+    pub fn ascii_lowercase() -> Self {
+        Self::new(|key| key.to_ascii_lowercase())
+    }
+
+    /// Removes leading and trailing whitespace.
+    pub fn trim() -> Self {
+        Self::new(|key| key.trim().to_string())
+    }
+
+    pub fn apply(&self, key: &str) -> String {
+        (self.0)(key)
+    }
+}
+
+impl fmt::Debug for KeyTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "KeyTransform")
+    }
+}
+
+/// Two transforms are equal only if they share the same function.
+impl PartialEq for KeyTransform {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Inserts an underscore at each word boundary and lowercases, treating runs of capitals as
+/// one word, e.g. `HTTPServerId` becomes `http_server_id`.
+pub fn to_snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut result = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+/// Removes each underscore and capitalizes the character following it. Leading underscores are
+/// kept, e.g. `_user_id` becomes `_userId`.
+pub fn to_camel_case(s: &str) -> String {
+    let body = s.trim_start_matches('_');
+    let mut result = s[..(s.len() - body.len())].to_string();
+    let mut capitalize = false;
+    for c in body.chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            result.extend(c.to_uppercase());
+            capitalize = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snake_case() {
+        assert_eq!("user_id", to_snake_case("userId"));
+        assert_eq!("user_id", to_snake_case("UserId"));
+        assert_eq!("http_server_id", to_snake_case("HTTPServerId"));
+        assert_eq!("v2_api", to_snake_case("v2Api"));
+        assert_eq!("already_snake", to_snake_case("already_snake"));
+    }
+
+    #[test]
+    fn camel_case() {
+        assert_eq!("userId", to_camel_case("user_id"));
+        assert_eq!("_userId", to_camel_case("_user_id"));
+        assert_eq!("alreadyCamel", to_camel_case("alreadyCamel"));
+    }
+
+    #[test]
+    fn builtins() {
+        assert_eq!("abc", KeyTransform::ascii_lowercase().apply("AbC"));
+        assert_eq!("a b", KeyTransform::trim().apply("  a b "));
+    }
+}
@@ -0,0 +1,200 @@
+/// Builds a `JSONValue` from JSON-like syntax. Values that are not literals are Rust expressions
+/// converted with `JSONValue::from`, and object keys are expressions converted into `Key`.
+///
+/// ```
+/// use rust_json::json;
+///
+/// let name = "Alice";
+/// let value = json!({
+///     "name": name,
+///     "tags": ["a", "b"],
+///     "age": 15,
+///     "manager": null
+/// });
+/// assert_eq!(value.get_as_object("age").and_then(|v| v.as_i64()), Some(15));
+/// ```
+#[macro_export]
+macro_rules! json {
+    ($($json:tt)+) => {
+        $crate::json_internal!($($json)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    // Array elements are munched one at a time into `[$($elems,)*]`. Literals and nested
+    // arrays or objects are matched before the general `expr` case, since once captured as an
+    // `expr` they would no longer match the literal rules below.
+    (@array [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+
+    (@array [$($elems:expr),*]) => {
+        vec![$($elems),*]
+    };
+
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(null)] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(true)] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(false)] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!([$($array)*])] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] {$($map:tt)*} $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!({$($map)*})] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($next),] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($last)])
+    };
+
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // Object entries are munched as `(key tokens) (remaining tokens)`, then once the value is
+    // known as `[key tokens] (value) remaining tokens`.
+    (@object $object:ident () ()) => {};
+
+    (@object $object:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        $object.insert(($($key)+), $value);
+        $crate::json_internal!(@object $object () ($($rest)*));
+    };
+
+    (@object $object:ident [$($key:tt)+] ($value:expr)) => {
+        $object.insert(($($key)+), $value);
+    };
+
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(null)) $($rest)*);
+    };
+
+    (@object $object:ident ($($key:tt)+) (: true $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(true)) $($rest)*);
+    };
+
+    (@object $object:ident ($($key:tt)+) (: false $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(false)) $($rest)*);
+    };
+
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!([$($array)*])) $($rest)*);
+    };
+
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!({$($map)*})) $($rest)*);
+    };
+
+    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)) , $($rest)*);
+    };
+
+    (@object $object:ident ($($key:tt)+) (: $value:expr)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)));
+    };
+
+    (@object $object:ident () (($key:expr) : $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object ($key) (: $($rest)*));
+    };
+
+    (@object $object:ident ($($key:tt)*) ($tt:tt $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object ($($key)* $tt) ($($rest)*));
+    };
+
+    (null) => {
+        $crate::json::json_value::JSONValue::Null
+    };
+
+    (true) => {
+        $crate::json::json_value::JSONValue::True
+    };
+
+    (false) => {
+        $crate::json::json_value::JSONValue::False
+    };
+
+    ([]) => {
+        $crate::json::json_value::JSONValue::Array(vec![])
+    };
+
+    ([ $($tt:tt)+ ]) => {
+        $crate::json::json_value::JSONValue::Array($crate::json_internal!(@array [] $($tt)+))
+    };
+
+    ({}) => {
+        $crate::json::json_value::JSONValue::Object($crate::json::object::JSONObject::new())
+    };
+
+    ({ $($tt:tt)+ }) => {
+        $crate::json::json_value::JSONValue::Object({
+            let mut object = $crate::json::object::JSONObject::new();
+            $crate::json_internal!(@object object () ($($tt)+));
+            object
+        })
+    };
+
+    ($other:expr) => {
+        $crate::json::json_value::JSONValue::from($other)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::json_value::JSONValue;
+    use super::super::parser::Parser;
+
+    #[test]
+    fn literals() {
+        assert_eq!(JSONValue::Null, json!(null));
+        assert_eq!(JSONValue::True, json!(true));
+        assert_eq!(JSONValue::False, json!(false));
+        assert_eq!(JSONValue::from(1.5), json!(1.5));
+        assert_eq!(JSONValue::from("a"), json!("a"));
+    }
+
+    #[test]
+    fn nested() {
+        let name = "Alice";
+        let tags = vec!["x", "y"];
+        let value = json!({
+            "name": name,
+            "age": 10 + 5,
+            "tags": tags,
+            "flags": [true, false, null, [], {}],
+            "nested": { "a": [1, { "b": null }], },
+            ("dyn".to_string() + "amic"): -1,
+        });
+
+        assert_eq!(
+            Parser::parse(
+                "{\"name\": \"Alice\", \"age\": 15, \"tags\": [\"x\", \"y\"],
+                  \"flags\": [true, false, null, [], {}],
+                  \"nested\": {\"a\": [1, {\"b\": null}]}, \"dynamic\": -1}"
+            ),
+            Ok(value)
+        );
+    }
+
+    #[test]
+    fn array_expressions() {
+        let x = 2;
+        assert_eq!(
+            JSONValue::from(vec![1, 4, 3]),
+            json!([1, x * 2, if x > 1 { 3 } else { 0 },])
+        );
+    }
+}
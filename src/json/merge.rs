@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::json_value::{JSONValue, Key};
+use super::path::{Path, PathSegment};
+
+/// How `JSONValue::merge_with` combines two arrays.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum ArrayMerge {
+    /// The incoming array replaces the existing one.
+    #[default]
+    Replace,
+    /// The incoming elements are appended.
+    Concat,
+    /// The incoming elements are appended unless an equal element is already present.
+    Union,
+    /// Elements at the same index are merged; surplus incoming elements are appended.
+    ByIndex,
+    /// Objects with an equal value for the given member are merged; other incoming elements are
+    /// appended.
+    ByKey(Key),
+}
+
+/// What to do when a value would be merged with a value of a different type, such as a string
+/// into an object. `null` on either side is never a conflict.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum TypeConflict {
+    /// The incoming value replaces the existing one.
+    #[default]
+    Overwrite,
+    /// Fails with a `MergeError` and leaves the target unchanged.
+    Error,
+}
+
+/// What an incoming `null` object member does.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum NullMerge {
+    /// Stores `null` like any other value.
+    #[default]
+    Set,
+    /// Removes the member from the target.
+    Delete,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MergeStrategy {
+    pub arrays: ArrayMerge,
+    pub type_conflicts: TypeConflict,
+    pub nulls: NullMerge,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MergeOptions {
+    /// Used everywhere no entry of `paths` applies.
+    pub strategy: MergeStrategy,
+    /// Strategies for the values at the given paths and everything below them. The entry for the
+    /// longest matching path wins.
+    pub paths: HashMap<Path, MergeStrategy>,
+}
+
+impl MergeOptions {
+    pub fn new(strategy: MergeStrategy) -> Self {
+        Self {
+            strategy,
+            paths: HashMap::new(),
+        }
+    }
+
+    /// Uses `strategy` for the value at `path` and everything below it.
+    pub fn at(mut self, path: impl Into<Path>, strategy: MergeStrategy) -> Self {
+        self.paths.insert(path.into(), strategy);
+        self
+    }
+
+    fn can_fail(&self) -> bool {
+        std::iter::once(&self.strategy)
+            .chain(self.paths.values())
+            .any(|s| s.type_conflicts == TypeConflict::Error)
+    }
+
+    fn strategy_at<'a>(&'a self, path: &Path, inherited: &'a MergeStrategy) -> &'a MergeStrategy {
+        self.paths.get(path).unwrap_or(inherited)
+    }
+
+    fn merge_value(
+        &self,
+        target: &mut JSONValue,
+        incoming: JSONValue,
+        path: &mut Path,
+        inherited: &MergeStrategy,
+    ) -> Result<(), MergeError> {
+        let strategy = self.strategy_at(path, inherited);
+        match (&mut *target, incoming) {
+            (JSONValue::Object(target), JSONValue::Object(incoming)) => {
+                for (key, value) in incoming {
+                    path.push(PathSegment::Key(key.clone()));
+                    let delete = value.is_null()
+                        && self.strategy_at(path, strategy).nulls == NullMerge::Delete;
+                    if delete {
+                        target.remove(&key);
+                    } else if let Some(existing) = target.get_mut(&key) {
+                        self.merge_value(existing, value, path, strategy)?;
+                    } else {
+                        target.insert(key, value);
+                    }
+                    path.pop();
+                }
+            }
+            (JSONValue::Array(target), JSONValue::Array(incoming)) => {
+                self.merge_array(target, incoming, path, strategy)?;
+            }
+            (_, incoming) => {
+                let conflict = !target.is_null()
+                    && !incoming.is_null()
+                    && target.type_name() != incoming.type_name();
+                if conflict && strategy.type_conflicts == TypeConflict::Error {
+                    return Err(MergeError {
+                        path: path.clone(),
+                        existing: target.type_name(),
+                        incoming: incoming.type_name(),
+                    });
+                }
+                *target = incoming;
+            }
+        }
+        Ok(())
+    }
+
+    fn merge_array(
+        &self,
+        target: &mut Vec<JSONValue>,
+        incoming: Vec<JSONValue>,
+        path: &mut Path,
+        strategy: &MergeStrategy,
+    ) -> Result<(), MergeError> {
+        match &strategy.arrays {
+            ArrayMerge::Replace => *target = incoming,
+            ArrayMerge::Concat => target.extend(incoming),
+            ArrayMerge::Union => {
+                for value in incoming {
+                    if !target.contains(&value) {
+                        target.push(value);
+                    }
+                }
+            }
+            ArrayMerge::ByIndex => {
+                for (i, value) in incoming.into_iter().enumerate() {
+                    match target.get_mut(i) {
+                        Some(existing) => {
+                            path.push(PathSegment::Index(i));
+                            self.merge_value(existing, value, path, strategy)?;
+                            path.pop();
+                        }
+                        None => target.push(value),
+                    }
+                }
+            }
+            ArrayMerge::ByKey(field) => {
+                for value in incoming {
+                    let position = value.get_as_object(field).and_then(|id| {
+                        target
+                            .iter()
+                            .position(|existing| existing.get_as_object(field) == Some(id))
+                    });
+                    match position {
+                        Some(i) => {
+                            path.push(PathSegment::Index(i));
+                            self.merge_value(&mut target[i], value, path, strategy)?;
+                            path.pop();
+                        }
+                        None => target.push(value),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reports a type conflict under `TypeConflict::Error`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MergeError {
+    path: Path,
+    existing: &'static str,
+    incoming: &'static str,
+}
+
+impl MergeError {
+    /// The path of the existing value in the merge target.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn existing(&self) -> &'static str {
+        self.existing
+    }
+
+    pub fn incoming(&self) -> &'static str {
+        self.incoming
+    }
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: cannot merge {} into {}",
+            self.path, self.incoming, self.existing
+        )
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+impl JSONValue {
+    /// Recursively merges `other` into this value with the default `MergeStrategy`: objects are
+    /// merged member by member, and any other value in `other` replaces the existing one.
+    pub fn merge(&mut self, other: JSONValue) {
+        self.merge_with(other, &MergeOptions::default())
+            .expect("the default strategy never fails");
+    }
+
+    /// Recursively merges `other` into this value as configured by `options`. On error the value
+    /// is left unchanged.
+    ///
+    /// ```
+    /// use rust_json::json;
+    /// use rust_json::json::merge::{ArrayMerge, MergeOptions, MergeStrategy, NullMerge};
+    ///
+    /// let mut config = json!({"hosts": ["a"], "debug": true, "port": 80});
+    /// let options = MergeOptions::new(MergeStrategy {
+    ///     arrays: ArrayMerge::Union,
+    ///     nulls: NullMerge::Delete,
+    ///     ..MergeStrategy::default()
+    /// });
+    /// config.merge_with(json!({"hosts": ["a", "b"], "debug": null}), &options).unwrap();
+    /// assert_eq!(json!({"hosts": ["a", "b"], "port": 80}), config);
+    /// ```
+    pub fn merge_with(
+        &mut self,
+        other: JSONValue,
+        options: &MergeOptions,
+    ) -> Result<(), MergeError> {
+        let mut path = Path::root();
+        if options.can_fail() {
+            let mut merged = self.clone();
+            options.merge_value(&mut merged, other, &mut path, &options.strategy)?;
+            *self = merged;
+            Ok(())
+        } else {
+            options.merge_value(self, other, &mut path, &options.strategy)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    fn strategy(arrays: ArrayMerge) -> MergeStrategy {
+        MergeStrategy {
+            arrays,
+            ..MergeStrategy::default()
+        }
+    }
+
+    #[test]
+    fn objects() {
+        let mut value = json!({"a": {"b": 1, "c": 2}, "d": [1]});
+        value.merge(json!({"a": {"c": 3, "e": null}, "d": [2], "f": "x"}));
+        assert_eq!(
+            json!({"a": {"b": 1, "c": 3, "e": null}, "d": [2], "f": "x"}),
+            value
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        let merge = |arrays: ArrayMerge| {
+            let mut value = json!([1, {"id": 1, "a": 1}, 3]);
+            let options = MergeOptions::new(strategy(arrays));
+            value
+                .merge_with(json!([3, {"id": 1, "b": 2}]), &options)
+                .unwrap();
+            value
+        };
+
+        assert_eq!(json!([3, {"id": 1, "b": 2}]), merge(ArrayMerge::Replace));
+        assert_eq!(
+            json!([1, {"id": 1, "a": 1}, 3, 3, {"id": 1, "b": 2}]),
+            merge(ArrayMerge::Concat)
+        );
+        assert_eq!(
+            json!([1, {"id": 1, "a": 1}, 3, {"id": 1, "b": 2}]),
+            merge(ArrayMerge::Union)
+        );
+        assert_eq!(
+            json!([3, {"id": 1, "a": 1, "b": 2}, 3]),
+            merge(ArrayMerge::ByIndex)
+        );
+        assert_eq!(
+            json!([1, {"id": 1, "a": 1, "b": 2}, 3, 3]),
+            merge(ArrayMerge::ByKey("id".into()))
+        );
+    }
+
+    #[test]
+    fn type_conflicts() {
+        let mut value = json!({"a": {"b": [1]}, "c": null});
+        let options = MergeOptions::new(MergeStrategy {
+            type_conflicts: TypeConflict::Error,
+            ..MergeStrategy::default()
+        });
+
+        let err = value
+            .merge_with(json!({"c": 1, "a": {"b": "x"}}), &options)
+            .unwrap_err();
+        assert_eq!("$.a.b: cannot merge string into array", err.to_string());
+        assert_eq!(json!({"a": {"b": [1]}, "c": null}), value);
+
+        value.merge(json!({"a": {"b": "x"}}));
+        assert_eq!(json!({"a": {"b": "x"}, "c": null}), value);
+    }
+
+    #[test]
+    fn per_path() {
+        let mut value = json!({"keep": [1], "tags": [1], "env": {"A": "1", "B": "2"}});
+        let options = MergeOptions::new(strategy(ArrayMerge::Concat))
+            .at(Path::root().join("keep"), MergeStrategy::default())
+            .at(
+                Path::root().join("env"),
+                MergeStrategy {
+                    nulls: NullMerge::Delete,
+                    ..MergeStrategy::default()
+                },
+            );
+
+        value
+            .merge_with(
+                json!({"keep": [2], "tags": [2], "env": {"A": null}, "x": null}),
+                &options,
+            )
+            .unwrap();
+        assert_eq!(
+            json!({"keep": [2], "tags": [1, 2], "env": {"B": "2"}, "x": null}),
+            value
+        );
+    }
+}
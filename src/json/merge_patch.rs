@@ -0,0 +1,184 @@
+use super::json_value::JSONValue;
+use super::object::JSONObject;
+
+/// Applies an RFC 7396 JSON Merge Patch to `target`. An object patch is merged member by member,
+/// where `null` removes the member and objects are merged recursively; any other patch replaces
+/// the target.
+///
+/// ```
+/// use rust_json::json;
+/// use rust_json::json::merge_patch::merge_patch;
+///
+/// let mut doc = json!({"title": "Hello", "author": {"name": "Ann", "email": "a@b"}, "tags": ["x"]});
+/// merge_patch(&mut doc, &json!({"title": "Bye", "author": {"email": null}, "tags": ["y"]}));
+/// assert_eq!(json!({"title": "Bye", "author": {"name": "Ann"}, "tags": ["y"]}), doc);
+/// ```
+pub fn merge_patch(target: &mut JSONValue, patch: &JSONValue) {
+    let JSONValue::Object(members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = JSONValue::Object(JSONObject::new());
+    }
+    let JSONValue::Object(object) = target else {
+        unreachable!()
+    };
+    for (key, value) in members.iter() {
+        if value.is_null() {
+            object.remove(key);
+        } else {
+            let slot = object.entry(key.clone()).or_default();
+            merge_patch(slot, value);
+        }
+    }
+}
+
+/// Computes the smallest merge patch that turns `source` into `target`: unchanged members are
+/// left out, removed members become `null`, and anything that is not an object on both sides is
+/// replaced whole.
+///
+/// Merge patches cannot set a member to `null`, so such members of `target` are removed or left
+/// out instead.
+pub fn create_merge_patch(source: &JSONValue, target: &JSONValue) -> JSONValue {
+    let (JSONValue::Object(source), JSONValue::Object(target)) = (source, target) else {
+        return target.clone();
+    };
+    let mut patch = JSONObject::new();
+    for key in source.keys() {
+        if !target.contains_key(key) {
+            patch.insert(key.clone(), JSONValue::Null);
+        }
+    }
+    for (key, value) in target.iter() {
+        match source.get(key) {
+            Some(old) if old == value => {}
+            Some(old) if old.is_object() && value.is_object() => {
+                let nested = create_merge_patch(old, value);
+                if nested.as_object().is_some_and(|nested| !nested.is_empty()) {
+                    patch.insert(key.clone(), nested);
+                }
+            }
+            Some(_) => {
+                patch.insert(key.clone(), value.clone());
+            }
+            None if value.is_null() => {}
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    JSONValue::Object(patch)
+}
+
+impl JSONValue {
+    /// Applies an RFC 7396 merge patch; see `merge_patch`.
+    pub fn merge_patch(&mut self, patch: &JSONValue) {
+        merge_patch(self, patch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn rfc_examples() {
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": "b"}),
+                json!({"b": "c"}),
+                json!({"a": "b", "b": "c"}),
+            ),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (
+                json!({"a": "b", "b": "c"}),
+                json!({"a": null}),
+                json!({"b": "c"}),
+            ),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"a": "foo"}), json!(null), json!(null)),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!([1, 2]),
+                json!({"a": "b", "c": null}),
+                json!({"a": "b"}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+        ];
+        for (mut target, patch, expected) in cases {
+            target.merge_patch(&patch);
+            assert_eq!(expected, target, "patch {:?}", patch);
+        }
+    }
+
+    #[test]
+    fn create() {
+        let source = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        let target = json!({
+            "title": "Hello!",
+            "author": {"givenName": "John"},
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "phoneNumber": "+01-123-456-7890"
+        });
+        let patch = create_merge_patch(&source, &target);
+        assert_eq!(
+            json!({
+                "title": "Hello!",
+                "author": {"familyName": null},
+                "tags": ["example"],
+                "phoneNumber": "+01-123-456-7890"
+            }),
+            patch
+        );
+
+        let mut patched = source.clone();
+        patched.merge_patch(&patch);
+        assert_eq!(target, patched);
+
+        assert_eq!(json!({}), create_merge_patch(&source, &source));
+        assert_eq!(json!([1]), create_merge_patch(&source, &json!([1])));
+        assert_eq!(
+            json!({"a": {}}),
+            create_merge_patch(&json!({"a": 1}), &json!({"a": {}}))
+        );
+        assert_eq!(
+            json!({"a": null}),
+            create_merge_patch(&json!({"a": 1}), &json!({"a": null, "b": null}))
+        );
+        assert_eq!(
+            json!({}),
+            create_merge_patch(&json!({"a": {"x": 1}}), &json!({"a": {"x": 1, "n": null}}))
+        );
+    }
+}
@@ -1,7 +1,33 @@
+pub mod access;
+pub mod conversion;
+pub mod diff;
+pub mod flatten;
+mod index;
+pub mod interner;
+pub mod jq;
+pub mod json_path;
 pub mod json_value;
+pub mod json_value_ref;
+pub mod key_transform;
+mod macros;
+pub mod merge;
+pub mod merge_patch;
+pub mod nodes;
+pub mod object;
+pub mod object_ref;
+mod ordering;
 pub mod parser;
-mod parser_error;
+pub mod parser_error;
+pub mod parser_options;
+pub mod patch;
+pub mod path;
+pub mod pointer;
+pub mod serializer;
+pub mod sql;
+pub mod stats;
 mod string;
 mod token;
 mod tokenizer;
-mod util;
+pub mod utf16_string;
+pub mod util;
+pub mod visit;
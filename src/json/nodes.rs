@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+
+use super::json_value::JSONValue;
+use super::path::{Path, PathSegment};
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Traversal {
+    /// Yields each value before its children, in document order.
+    #[default]
+    DepthFirst,
+    /// Yields all values at one depth before any value at the next.
+    BreadthFirst,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum NodeFilter {
+    #[default]
+    All,
+    /// Only values that are neither objects nor arrays.
+    Leaves,
+    /// Only objects and arrays, including empty ones.
+    Containers,
+}
+
+/// An iterator over the values of a tree paired with their paths, created by
+/// `JSONValue::nodes`. The root is yielded with the empty path.
+///
+/// The builder methods configure the iteration and should be called before the first `next`.
+#[derive(Debug, Clone)]
+pub struct Nodes<'a> {
+    pending: VecDeque<(Path, &'a JSONValue)>,
+    traversal: Traversal,
+    filter: NodeFilter,
+    max_depth: Option<usize>,
+}
+
+impl<'a> Nodes<'a> {
+    pub fn new(root: &'a JSONValue) -> Self {
+        Self {
+            pending: VecDeque::from([(Path::root(), root)]),
+            traversal: Traversal::default(),
+            filter: NodeFilter::default(),
+            max_depth: None,
+        }
+    }
+
+    pub fn traversal(mut self, traversal: Traversal) -> Self {
+        self.traversal = traversal;
+        self
+    }
+
+    pub fn depth_first(self) -> Self {
+        self.traversal(Traversal::DepthFirst)
+    }
+
+    pub fn breadth_first(self) -> Self {
+        self.traversal(Traversal::BreadthFirst)
+    }
+
+    pub fn select(mut self, filter: NodeFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn leaves(self) -> Self {
+        self.select(NodeFilter::Leaves)
+    }
+
+    pub fn containers(self) -> Self {
+        self.select(NodeFilter::Containers)
+    }
+
+    /// Does not descend below `depth`, where the root has depth 0 and its children depth 1.
+    /// Containers at `depth` are still yielded, but their children are not.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    fn expand(&mut self, path: &Path, value: &'a JSONValue) {
+        if self.max_depth.is_some_and(|max| path.len() >= max) {
+            return;
+        }
+
+        let children: Vec<(Path, &'a JSONValue)> = match value {
+            JSONValue::Object(obj) => obj
+                .iter()
+                .map(|(k, v)| (path.join(PathSegment::Key(k.clone())), v))
+                .collect(),
+            JSONValue::Array(arr) => arr
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (path.join(i), v))
+                .collect(),
+            _ => return,
+        };
+
+        match self.traversal {
+            Traversal::DepthFirst => {
+                for child in children.into_iter().rev() {
+                    self.pending.push_front(child);
+                }
+            }
+            Traversal::BreadthFirst => self.pending.extend(children),
+        }
+    }
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = (Path, &'a JSONValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, value)) = self.pending.pop_front() {
+            self.expand(&path, value);
+
+            let is_container = value.is_object() || value.is_array();
+            let selected = match self.filter {
+                NodeFilter::All => true,
+                NodeFilter::Leaves => !is_container,
+                NodeFilter::Containers => is_container,
+            };
+            if selected {
+                return Some((path, value));
+            }
+        }
+        None
+    }
+}
+
+impl JSONValue {
+    /// Iterates over this value and all values nested in it, with their paths; see `Nodes`.
+    pub fn nodes(&self) -> Nodes<'_> {
+        Nodes::new(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parser::Parser;
+    use super::*;
+
+    fn paths(nodes: Nodes) -> Vec<String> {
+        nodes.map(|(path, _)| path.to_string()).collect()
+    }
+
+    fn document() -> JSONValue {
+        Parser::parse("{\"a\": {\"b\": [1, 2]}, \"c\": 3}").unwrap()
+    }
+
+    #[test]
+    fn depth_first() {
+        let value = document();
+        assert_eq!(
+            vec!["$", "$.a", "$.a.b", "$.a.b[0]", "$.a.b[1]", "$.c"],
+            paths(value.nodes())
+        );
+    }
+
+    #[test]
+    fn breadth_first() {
+        let value = document();
+        assert_eq!(
+            vec!["$", "$.a", "$.c", "$.a.b", "$.a.b[0]", "$.a.b[1]"],
+            paths(value.nodes().breadth_first())
+        );
+    }
+
+    #[test]
+    fn filters() {
+        let value = document();
+        assert_eq!(
+            vec!["$.a.b[0]", "$.a.b[1]", "$.c"],
+            paths(value.nodes().leaves())
+        );
+        assert_eq!(vec!["$", "$.a", "$.a.b"], paths(value.nodes().containers()));
+    }
+
+    #[test]
+    fn max_depth() {
+        let value = document();
+        assert_eq!(
+            vec!["$", "$.a", "$.c", "$.a.b"],
+            paths(value.nodes().breadth_first().max_depth(2))
+        );
+        assert_eq!(vec!["$.c"], paths(value.nodes().leaves().max_depth(1)));
+        assert_eq!(
+            vec!["/a/b/0", "/a/b/1"],
+            value
+                .nodes()
+                .select(NodeFilter::Leaves)
+                .filter(|(_, v)| v.as_number().is_some())
+                .map(|(path, _)| path.to_pointer())
+                .take(2)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::json_value::{JSONValue, Key};
+
+/// The contents of a JSON object: a map from keys to values that remembers insertion order.
+///
+/// Lookups by key are O(1). Iteration yields entries in the order their keys were first
+/// inserted; replacing the value of an existing key keeps its position, and removing a key
+/// shifts the following entries up by one, which is O(n).
+///
+/// Two objects are equal if they hold the same entries, regardless of order.
+#[derive(Clone, Default)]
+pub struct JSONObject {
+    entries: Vec<(Key, JSONValue)>,
+    indices: HashMap<Key, usize>,
+}
+
+impl JSONObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            indices: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JSONValue> {
+        self.indices.get(key).map(|i| &self.entries[*i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JSONValue> {
+        self.indices.get(key).map(|i| &mut self.entries[*i].1)
+    }
+
+    /// Returns the entry at `index` in iteration order.
+    pub fn get_index(&self, index: usize) -> Option<(&Key, &JSONValue)> {
+        self.entries.get(index).map(|(k, v)| (k, v))
+    }
+
+    /// Returns the position of `key` in iteration order.
+    pub fn index_of(&self, key: &str) -> Option<usize> {
+        self.indices.get(key).copied()
+    }
+
+    /// Inserts a value, returning the previous value for the key if there was one. A new key is
+    /// appended at the end; an existing key keeps its position.
+    pub fn insert(&mut self, key: impl Into<Key>, value: JSONValue) -> Option<JSONValue> {
+        let key = key.into();
+        match self.indices.get(&key) {
+            Some(i) => Some(std::mem::replace(&mut self.entries[*i].1, value)),
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes a key, preserving the order of the remaining entries.
+    pub fn remove(&mut self, key: &str) -> Option<JSONValue> {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry(&mut self, key: &str) -> Option<(Key, JSONValue)> {
+        let index = self.indices.remove(key)?;
+        let entry = self.entries.remove(index);
+        for (k, _) in &self.entries[index..] {
+            *self.indices.get_mut(k).unwrap() -= 1;
+        }
+        Some(entry)
+    }
+
+    /// Keeps only the entries for which `f` returns `true`, preserving their order.
+    pub fn retain(&mut self, mut f: impl FnMut(&Key, &mut JSONValue) -> bool) {
+        self.entries.retain_mut(|(k, v)| f(k, v));
+        self.indices.clear();
+        for (i, (k, _)) in self.entries.iter().enumerate() {
+            self.indices.insert(k.clone(), i);
+        }
+    }
+
+    pub fn entry(&mut self, key: impl Into<Key>) -> Entry<'_> {
+        let key = key.into();
+        match self.indices.get(&key) {
+            Some(index) => Entry::Occupied(OccupiedEntry {
+                index: *index,
+                object: self,
+            }),
+            None => Entry::Vacant(VacantEntry { key, object: self }),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &JSONValue> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut JSONValue> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Estimates the heap bytes of the entry list and the key index, not counting the
+    /// allocations of the keys and values themselves.
+    pub(crate) fn table_heap_bytes(&self) -> usize {
+        use std::mem::size_of;
+
+        let entries = self.entries.capacity() * size_of::<(Key, JSONValue)>();
+        // The standard hash table keeps its load factor at 7/8 in power-of-two bucket arrays,
+        // with one control byte per bucket plus one trailing group of 16.
+        let buckets = match self.indices.capacity() {
+            0 => return entries,
+            cap if cap < 7 => cap + 1,
+            cap => (cap * 8 / 7).next_power_of_two(),
+        };
+        entries + buckets * (size_of::<(Key, usize)>() + 1) + 16
+    }
+}
+
+impl PartialEq for JSONObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl fmt::Debug for JSONObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Into<Key>> FromIterator<(K, JSONValue)> for JSONObject {
+    fn from_iter<I: IntoIterator<Item = (K, JSONValue)>>(iter: I) -> Self {
+        let mut object = JSONObject::new();
+        object.extend(iter);
+        object
+    }
+}
+
+impl<K: Into<Key>> Extend<(K, JSONValue)> for JSONObject {
+    fn extend<I: IntoIterator<Item = (K, JSONValue)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+/// A view into a single entry of a `JSONObject`, returned by `JSONObject::entry`.
+pub enum Entry<'a> {
+    Occupied(OccupiedEntry<'a>),
+    Vacant(VacantEntry<'a>),
+}
+
+impl<'a> Entry<'a> {
+    pub fn key(&self) -> &Key {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: JSONValue) -> &'a mut JSONValue {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> JSONValue) -> &'a mut JSONValue {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Inserts `Null` if the entry is vacant.
+    pub fn or_default(self) -> &'a mut JSONValue {
+        self.or_insert(JSONValue::Null)
+    }
+
+    pub fn and_modify(mut self, f: impl FnOnce(&mut JSONValue)) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+pub struct OccupiedEntry<'a> {
+    object: &'a mut JSONObject,
+    index: usize,
+}
+
+impl<'a> OccupiedEntry<'a> {
+    pub fn key(&self) -> &Key {
+        &self.object.entries[self.index].0
+    }
+
+    pub fn get(&self) -> &JSONValue {
+        &self.object.entries[self.index].1
+    }
+
+    pub fn get_mut(&mut self) -> &mut JSONValue {
+        &mut self.object.entries[self.index].1
+    }
+
+    pub fn into_mut(self) -> &'a mut JSONValue {
+        &mut self.object.entries[self.index].1
+    }
+
+    /// Replaces the value, returning the previous one.
+    pub fn insert(&mut self, value: JSONValue) -> JSONValue {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry, preserving the order of the remaining entries.
+    pub fn remove(self) -> JSONValue {
+        let key = self.key().clone();
+        self.object.remove(&key).unwrap()
+    }
+}
+
+pub struct VacantEntry<'a> {
+    object: &'a mut JSONObject,
+    key: Key,
+}
+
+impl<'a> VacantEntry<'a> {
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    /// Appends the entry at the end of the object.
+    pub fn insert(self, value: JSONValue) -> &'a mut JSONValue {
+        let index = self.object.entries.len();
+        self.object.insert(self.key, value);
+        &mut self.object.entries[index].1
+    }
+}
+
+pub struct Iter<'a>(std::slice::Iter<'a, (Key, JSONValue)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a Key, &'a JSONValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+pub struct IterMut<'a>(std::slice::IterMut<'a, (Key, JSONValue)>);
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a Key, &'a mut JSONValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+impl<'a> IntoIterator for &'a JSONObject {
+    type Item = (&'a Key, &'a JSONValue);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut JSONObject {
+    type Item = (&'a Key, &'a mut JSONValue);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for JSONObject {
+    type Item = (Key, JSONValue);
+    type IntoIter = std::vec::IntoIter<(Key, JSONValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keys(object: &JSONObject) -> Vec<&str> {
+        object.keys().map(|k| &**k).collect()
+    }
+
+    #[test]
+    fn insertion_order() {
+        let mut object = JSONObject::new();
+        object.insert("b", JSONValue::Null);
+        object.insert("a", JSONValue::Null);
+        object.insert("c", JSONValue::Null);
+        assert_eq!(vec!["b", "a", "c"], keys(&object));
+
+        assert_eq!(Some(JSONValue::Null), object.insert("a", JSONValue::True));
+        assert_eq!(vec!["b", "a", "c"], keys(&object));
+        assert_eq!(Some(&JSONValue::True), object.get("a"));
+    }
+
+    #[test]
+    fn remove_keeps_order() {
+        let mut object: JSONObject = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|k| (k, JSONValue::Null))
+            .collect();
+
+        assert_eq!(Some(JSONValue::Null), object.remove("b"));
+        assert_eq!(None, object.remove("b"));
+        assert_eq!(vec!["a", "c", "d"], keys(&object));
+        assert_eq!(Some(2), object.index_of("d"));
+        assert!(object.contains_key("d"));
+    }
+
+    #[test]
+    fn retain() {
+        let mut object: JSONObject = ["a", "b", "c"]
+            .into_iter()
+            .map(|k| (k, JSONValue::Null))
+            .collect();
+
+        object.retain(|k, _| &**k != "a");
+        assert_eq!(vec!["b", "c"], keys(&object));
+        assert_eq!(Some(0), object.index_of("b"));
+    }
+
+    #[test]
+    fn entry() {
+        let mut object = JSONObject::new();
+        object.insert("count", JSONValue::from(1));
+
+        object
+            .entry("count")
+            .and_modify(|v| *v = JSONValue::from(2))
+            .or_insert(JSONValue::from(0));
+        object.entry("new").or_insert_with(|| JSONValue::True);
+        *object.entry("other").or_default() = JSONValue::False;
+
+        assert_eq!(Some(&JSONValue::from(2)), object.get("count"));
+        assert_eq!(Some(&JSONValue::True), object.get("new"));
+        assert_eq!(vec!["count", "new", "other"], keys(&object));
+
+        if let Entry::Occupied(entry) = object.entry("count") {
+            assert_eq!(JSONValue::from(2), entry.remove());
+        }
+        assert_eq!(vec!["new", "other"], keys(&object));
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let a: JSONObject = [("x", JSONValue::True), ("y", JSONValue::False)]
+            .into_iter()
+            .collect();
+        let b: JSONObject = [("y", JSONValue::False), ("x", JSONValue::True)]
+            .into_iter()
+            .collect();
+        let c: JSONObject = [("x", JSONValue::True)].into_iter().collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::json_value_ref::JSONValueRef;
+
+/// The contents of a borrowed JSON object, with the same ordering and equality semantics as
+/// `JSONObject`. Keys borrow from the input text unless they contained escape sequences.
+#[derive(Clone, Default)]
+pub struct JSONObjectRef<'a> {
+    entries: Vec<(Cow<'a, str>, JSONValueRef<'a>)>,
+    indices: HashMap<Cow<'a, str>, usize>,
+}
+
+impl<'a> JSONObjectRef<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JSONValueRef<'a>> {
+        self.indices.get(key).map(|i| &self.entries[*i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JSONValueRef<'a>> {
+        self.indices.get(key).map(|i| &mut self.entries[*i].1)
+    }
+
+    /// Inserts a value, returning the previous value for the key if there was one. A new key is
+    /// appended at the end; an existing key keeps its position.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: JSONValueRef<'a>,
+    ) -> Option<JSONValueRef<'a>> {
+        let key = key.into();
+        match self.indices.get(&key) {
+            Some(i) => Some(std::mem::replace(&mut self.entries[*i].1, value)),
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Cow<'a, str>, &JSONValueRef<'a>)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Cow<'a, str>> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &JSONValueRef<'a>> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+impl PartialEq for JSONObjectRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl fmt::Debug for JSONObjectRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for JSONObjectRef<'a> {
+    type Item = (Cow<'a, str>, JSONValueRef<'a>);
+    type IntoIter = std::vec::IntoIter<(Cow<'a, str>, JSONValueRef<'a>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
@@ -0,0 +1,97 @@
+use std::cmp::Ordering;
+
+use super::json_value::JSONValue;
+use super::object::JSONObject;
+use super::util::signed_num_64::SignedNum64;
+
+/// jq's total order: null < false < true < numbers < strings < arrays < objects. Numbers compare
+/// by value, so `1` equals `1.0`. Objects compare their sorted key lists first, then their values
+/// key by key.
+///
+/// Shared by jq, the SQL queries, JSONPath filters and the JSON Patch `test` operation so that
+/// they all agree on equality.
+pub(crate) fn compare(left: &JSONValue, right: &JSONValue) -> Ordering {
+    fn rank(value: &JSONValue) -> u8 {
+        match value {
+            JSONValue::Null => 0,
+            JSONValue::False => 1,
+            JSONValue::True => 2,
+            JSONValue::Number(_) => 3,
+            JSONValue::String(_) | JSONValue::Utf16String(_) => 4,
+            JSONValue::Array(_) => 5,
+            JSONValue::Object(_) => 6,
+        }
+    }
+
+    match (left, right) {
+        (
+            JSONValue::Number(SignedNum64::Integer(l)),
+            JSONValue::Number(SignedNum64::Integer(r)),
+        ) => l.cmp(r),
+        (JSONValue::Number(l), JSONValue::Number(r)) => l
+            .as_f64()
+            .partial_cmp(&r.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (JSONValue::Array(l), JSONValue::Array(r)) => compare_slices(l, r),
+        (JSONValue::Object(l), JSONValue::Object(r)) => {
+            let sorted_keys = |obj: &JSONObject| {
+                let mut keys: Vec<_> = obj.keys().cloned().collect();
+                keys.sort();
+                keys
+            };
+            let (l_keys, r_keys) = (sorted_keys(l), sorted_keys(r));
+            l_keys.cmp(&r_keys).then_with(|| {
+                l_keys
+                    .iter()
+                    .map(|key| compare(l.get(key).unwrap(), r.get(key).unwrap()))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ if rank(left) == 4 && rank(right) == 4 => left.as_string().cmp(&right.as_string()),
+        _ => rank(left).cmp(&rank(right)),
+    }
+}
+
+/// Compares arrays element by element, then by length.
+pub(crate) fn compare_slices(left: &[JSONValue], right: &[JSONValue]) -> Ordering {
+    left.iter()
+        .zip(right)
+        .map(|(l, r)| compare(l, r))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| left.len().cmp(&right.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn total_order() {
+        let ordered = [
+            json!(null),
+            json!(false),
+            json!(true),
+            json!(-1),
+            json!(0.5),
+            json!(2),
+            json!("a"),
+            json!("b"),
+            json!([]),
+            json!([1]),
+            json!([1, 2]),
+            json!([2]),
+            json!({}),
+            json!({"a": 2}),
+            json!({"a": 1, "b": 0}),
+        ];
+        for (i, left) in ordered.iter().enumerate() {
+            for (j, right) in ordered.iter().enumerate() {
+                assert_eq!(i.cmp(&j), compare(left, right), "{:?} {:?}", left, right);
+            }
+        }
+        assert!(compare(&json!(1), &json!(1.0)).is_eq());
+        assert!(compare(&json!({"a": [1], "b": 2}), &json!({"b": 2.0, "a": [1.0]})).is_eq());
+    }
+}
@@ -1,50 +1,232 @@
-use std::collections::HashMap;
-use std::iter::Peekable;
-
-use super::json_value::JSONValue;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::interner::Interner;
+use super::json_value::{JSONValue, Key};
+use super::json_value_ref::JSONValueRef;
+use super::object::JSONObject;
+use super::object_ref::JSONObjectRef;
 use super::parser_error::{ParserError, ParserErrorKind};
+use super::parser_options::{DuplicateKeys, ParserOptions};
 use super::token::Token;
 use super::tokenizer::Tokenizer;
 
+/// A value tree the parser can build: owned `JSONValue`s or borrowed `JSONValueRef`s.
+trait Tree<'t>: Sized {
+    type Key: Clone + Eq + Hash + Deref<Target = str>;
+    type Map: Default;
+
+    /// Converts a scalar token, or returns `None` for structural tokens.
+    fn scalar(token: &Token<'t>) -> Option<Self>;
+    fn key(key: Cow<'t, str>, interner: Option<&mut Interner>) -> Self::Key;
+    fn array(values: Vec<Self>) -> Self;
+    fn object(object: Self::Map) -> Self;
+    fn object_get_mut<'o>(object: &'o mut Self::Map, key: &str) -> Option<&'o mut Self>;
+    fn object_insert(object: &mut Self::Map, key: Self::Key, value: Self);
+    fn array_mut(&mut self) -> Option<&mut Vec<Self>>;
+}
+
+impl<'t> Tree<'t> for JSONValue {
+    type Key = Key;
+    type Map = JSONObject;
+
+    fn scalar(token: &Token<'t>) -> Option<Self> {
+        match token {
+            Token::True => Some(JSONValue::True),
+            Token::False => Some(JSONValue::False),
+            Token::Null => Some(JSONValue::Null),
+            Token::Number(val) => Some(JSONValue::Number(*val)),
+            Token::String(val) => Some(JSONValue::String(val.to_string())),
+            Token::Utf16String(val) => Some(JSONValue::Utf16String(val.clone())),
+            _ => None,
+        }
+    }
+
+    fn key(key: Cow<'t, str>, interner: Option<&mut Interner>) -> Self::Key {
+        match interner {
+            Some(interner) => interner.intern(&key),
+            None => Arc::from(key),
+        }
+    }
+
+    fn array(values: Vec<Self>) -> Self {
+        JSONValue::Array(values)
+    }
+
+    fn object(object: Self::Map) -> Self {
+        JSONValue::Object(object)
+    }
+
+    fn object_get_mut<'o>(object: &'o mut Self::Map, key: &str) -> Option<&'o mut Self> {
+        object.get_mut(key)
+    }
+
+    fn object_insert(object: &mut Self::Map, key: Self::Key, value: Self) {
+        object.insert(key, value);
+    }
+
+    fn array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        self.as_array_mut()
+    }
+}
+
+impl<'t> Tree<'t> for JSONValueRef<'t> {
+    type Key = Cow<'t, str>;
+    type Map = JSONObjectRef<'t>;
+
+    fn scalar(token: &Token<'t>) -> Option<Self> {
+        match token {
+            Token::True => Some(JSONValueRef::True),
+            Token::False => Some(JSONValueRef::False),
+            Token::Null => Some(JSONValueRef::Null),
+            Token::Number(val) => Some(JSONValueRef::Number(*val)),
+            Token::String(val) => Some(JSONValueRef::String(val.clone())),
+            Token::Utf16String(val) => Some(JSONValueRef::Utf16String(val.clone())),
+            _ => None,
+        }
+    }
+
+    fn key(key: Cow<'t, str>, _: Option<&mut Interner>) -> Self::Key {
+        key
+    }
+
+    fn array(values: Vec<Self>) -> Self {
+        JSONValueRef::Array(values)
+    }
+
+    fn object(object: Self::Map) -> Self {
+        JSONValueRef::Object(object)
+    }
+
+    fn object_get_mut<'o>(object: &'o mut Self::Map, key: &str) -> Option<&'o mut Self> {
+        object.get_mut(key)
+    }
+
+    fn object_insert(object: &mut Self::Map, key: Self::Key, value: Self) {
+        object.insert(key, value);
+    }
+
+    fn array_mut(&mut self) -> Option<&mut Vec<Self>> {
+        match self {
+            JSONValueRef::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct Parser<'a> {
-    iter: Peekable<std::slice::Iter<'a, Token>>,
+pub struct Parser<'a, 't> {
+    tokens: &'a [(Token<'t>, usize)],
+    index: usize,
+    text_len: usize,
+    options: &'a ParserOptions,
+    interner: Option<Interner>,
 }
 
-impl<'a> Parser<'a> {
-    fn new(tokens: &'a Vec<Token>) -> Self {
+impl<'a, 't> Parser<'a, 't> {
+    fn new(tokens: &'a [(Token<'t>, usize)], text_len: usize, options: &'a ParserOptions) -> Self {
         Self {
-            iter: tokens.iter().peekable(),
+            tokens,
+            index: 0,
+            text_len,
+            options,
+            interner: options.intern_keys.then(Interner::new),
         }
-        .to_owned()
     }
 
     pub fn parse(text: &str) -> Result<JSONValue, ParserError> {
-        let tokens = Tokenizer::tokenize(text);
-        let mut parser = Parser::new(&tokens);
+        Self::parse_with_options(text, &ParserOptions::default())
+    }
+
+    pub fn parse_with_options(
+        text: &str,
+        options: &ParserOptions,
+    ) -> Result<JSONValue, ParserError> {
+        let tokens = Tokenizer::tokenize(text, options)?;
+        Parser::new(&tokens, text.len(), options).parse_document()
+    }
+
+    /// Parses `text` into a tree that borrows strings and keys from it instead of copying them.
+    pub fn parse_borrowed(text: &'t str) -> Result<JSONValueRef<'t>, ParserError> {
+        Self::parse_borrowed_with_options(text, &ParserOptions::default())
+    }
 
-        parser.parse_value().and_then(|token| {
-            if parser.iter.peek().is_none() {
+    /// Like `parse_borrowed`. Keys changed by `ParserOptions::key_transform` are allocated, and
+    /// `ParserOptions::intern_keys` has no effect.
+    pub fn parse_borrowed_with_options(
+        text: &'t str,
+        options: &ParserOptions,
+    ) -> Result<JSONValueRef<'t>, ParserError> {
+        let tokens = Tokenizer::tokenize(text, options)?;
+        Parser::new(&tokens, text.len(), options).parse_document()
+    }
+
+    fn parse_document<T: Tree<'t>>(&mut self) -> Result<T, ParserError> {
+        self.parse_value().and_then(|token| {
+            if self.peek().is_none() {
                 Ok(token)
             } else {
-                Err(ParserError::new(ParserErrorKind::UnexpectedToken))
+                Err(self.error(ParserErrorKind::UnexpectedToken))
             }
         })
     }
 
-    fn consume_token(&mut self, token: Token) -> Result<&Token, ParserError> {
-        (self.iter.next())
-            .filter(|v| **v == token)
-            .ok_or(ParserError::new(ParserErrorKind::UnexpectedToken))
+    fn peek(&self) -> Option<&'a Token<'t>> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<&'a Token<'t>> {
+        let token = self.peek();
+        self.index += 1;
+        token
     }
 
-    fn parse_key_value_pair(&mut self) -> Result<(String, JSONValue), ParserError> {
-        let key = (self.iter.next())
-            .and_then(|v| match v {
-                Token::String(val) => Some(val.to_owned()),
-                _ => None,
-            })
-            .ok_or(ParserError::new(ParserErrorKind::UnexpectedToken))?;
+    /// Byte offset of the next token, or the end of the text if all tokens are consumed.
+    fn position(&self) -> usize {
+        (self.tokens.get(self.index))
+            .map(|(_, position)| *position)
+            .unwrap_or(self.text_len)
+    }
+
+    fn error(&self, kind: ParserErrorKind) -> ParserError {
+        ParserError::with_position(kind, self.position())
+    }
+
+    /// Builds the error for an unexpected next token, which is `UnexpectedEOF` at the end.
+    fn unexpected(&self) -> ParserError {
+        match self.peek() {
+            Some(_) => self.error(ParserErrorKind::UnexpectedToken),
+            None => self.error(ParserErrorKind::UnexpectedEOF),
+        }
+    }
+
+    fn consume_token(&mut self, token: Token) -> Result<&Token<'t>, ParserError> {
+        match self.peek() {
+            Some(next) if *next == token => {
+                self.index += 1;
+                Ok(next)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_key_value_pair<T: Tree<'t>>(&mut self) -> Result<(T::Key, T), ParserError> {
+        let key = match self.peek() {
+            Some(Token::String(val)) => val.clone(),
+            Some(Token::Utf16String(_)) => {
+                return Err(self.error(ParserErrorKind::LoneSurrogateInKey))
+            }
+            _ => return Err(self.unexpected()),
+        };
+        self.index += 1;
+        let key = match &self.options.key_transform {
+            Some(transform) => Cow::Owned(transform.apply(&key)),
+            None => key,
+        };
+        let key = T::key(key, self.interner.as_mut());
 
         self.consume_token(Token::NameSeparator)?;
 
@@ -53,50 +235,84 @@ impl<'a> Parser<'a> {
         Ok((key, value))
     }
 
-    fn parse_object(&mut self) -> Result<JSONValue, ParserError> {
-        let mut contents: HashMap<String, JSONValue> = HashMap::new();
+    /// Inserts an entry into `contents` according to `ParserOptions::duplicate_keys`. `collected`
+    /// tracks the keys whose values have already been gathered into an array.
+    fn insert_entry<T: Tree<'t>>(
+        &self,
+        contents: &mut T::Map,
+        collected: &mut HashSet<T::Key>,
+        (key, value): (T::Key, T),
+        position: usize,
+    ) -> Result<(), ParserError> {
+        let Some(existing) = T::object_get_mut(contents, &key) else {
+            T::object_insert(contents, key, value);
+            return Ok(());
+        };
+
+        match self.options.duplicate_keys {
+            DuplicateKeys::Error => {
+                return Err(ParserError::with_position(
+                    ParserErrorKind::DuplicateKey(key.to_string()),
+                    position,
+                ))
+            }
+            DuplicateKeys::FirstWins => {}
+            DuplicateKeys::LastWins => *existing = value,
+            DuplicateKeys::CollectIntoArray => {
+                if collected.contains(&key) {
+                    if let Some(values) = existing.array_mut() {
+                        values.push(value);
+                    }
+                } else {
+                    let first = std::mem::replace(existing, T::array(Vec::new()));
+                    *existing = T::array(vec![first, value]);
+                    collected.insert(key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_object<T: Tree<'t>>(&mut self) -> Result<T, ParserError> {
+        let mut contents = T::Map::default();
+        let mut collected = HashSet::new();
 
         self.consume_token(Token::BeginObject)?;
 
-        if let Some(next) = self.iter.peek() {
-            if **next != Token::EndObject {
-                let next_entry = self.parse_key_value_pair()?;
-                contents.insert(next_entry.0, next_entry.1);
+        if let Some(next) = self.peek() {
+            if *next != Token::EndObject {
+                let position = self.position();
+                let next_entry = self.parse_key_value_pair::<T>()?;
+                self.insert_entry(&mut contents, &mut collected, next_entry, position)?;
 
-                loop {
-                    if let Some(Token::ValueSeparator) = self.iter.peek() {
-                        self.iter.next();
-                    } else {
-                        break;
-                    }
+                while let Some(Token::ValueSeparator) = self.peek() {
+                    self.next();
 
-                    let next_entry = self.parse_key_value_pair()?;
-                    contents.insert(next_entry.0, next_entry.1);
+                    let position = self.position();
+                    let next_entry = self.parse_key_value_pair::<T>()?;
+                    self.insert_entry(&mut contents, &mut collected, next_entry, position)?;
                 }
             }
         }
 
         self.consume_token(Token::EndObject)?;
 
-        Ok(JSONValue::Object(contents))
+        Ok(T::object(contents))
     }
 
-    fn parse_array(&mut self) -> Result<JSONValue, ParserError> {
-        let mut contents: Vec<JSONValue> = Vec::new();
+    fn parse_array<T: Tree<'t>>(&mut self) -> Result<T, ParserError> {
+        let mut contents: Vec<T> = Vec::new();
 
         self.consume_token(Token::BeginArray)?;
 
-        if let Some(next) = self.iter.peek() {
-            if **next != Token::EndArray {
+        if let Some(next) = self.peek() {
+            if *next != Token::EndArray {
                 let next_val = self.parse_value()?;
                 contents.push(next_val);
 
-                loop {
-                    if let Some(Token::ValueSeparator) = self.iter.peek() {
-                        self.iter.next();
-                    } else {
-                        break;
-                    }
+                while let Some(Token::ValueSeparator) = self.peek() {
+                    self.next();
 
                     let next_val = self.parse_value()?;
                     contents.push(next_val);
@@ -106,44 +322,33 @@ impl<'a> Parser<'a> {
 
         self.consume_token(Token::EndArray)?;
 
-        Ok(JSONValue::Array(contents))
+        Ok(T::array(contents))
     }
 
-    fn parse_value(&mut self) -> Result<JSONValue, ParserError> {
-        if let Some(next) = self.iter.peek() {
+    fn parse_value<T: Tree<'t>>(&mut self) -> Result<T, ParserError> {
+        if let Some(next) = self.peek() {
             match next {
-                Token::True => {
-                    self.iter.next();
-                    Ok(JSONValue::True)
-                }
-                Token::False => {
-                    self.iter.next();
-                    Ok(JSONValue::False)
-                }
-                Token::Null => {
-                    self.iter.next();
-                    Ok(JSONValue::Null)
-                }
-                Token::Number(val) => {
-                    self.iter.next();
-                    Ok(JSONValue::Number(val.clone()))
-                }
-                Token::String(val) => {
-                    self.iter.next();
-                    Ok(JSONValue::String(val.clone()))
-                }
                 Token::BeginArray => self.parse_array(),
                 Token::BeginObject => self.parse_object(),
-                _ => Err(ParserError::new(ParserErrorKind::UnexpectedToken)),
+                _ => match T::scalar(next) {
+                    Some(value) => {
+                        self.next();
+                        Ok(value)
+                    }
+                    None => Err(self.unexpected()),
+                },
             }
         } else {
-            Err(ParserError::new(ParserErrorKind::UnexpectedEOF))
+            Err(self.unexpected())
         }
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::super::key_transform::KeyTransform;
+    use super::super::parser_options::{DuplicateKeys, LoneSurrogates, StringValidation};
+    use super::super::utf16_string::Utf16String;
     use super::super::util::signed_num_64::SignedNum64;
     use super::*;
 
@@ -235,10 +440,10 @@ mod test {
 
     #[test]
     fn value_object() {
-        let mut map: HashMap<String, JSONValue> = HashMap::new();
-        map.insert("a".to_string(), JSONValue::Number(SignedNum64::Integer(0)));
-        map.insert("b".to_string(), JSONValue::True);
-        map.insert("c".to_string(), JSONValue::Null);
+        let mut map = JSONObject::new();
+        map.insert("a", JSONValue::Number(SignedNum64::Integer(0)));
+        map.insert("b", JSONValue::True);
+        map.insert("c", JSONValue::Null);
 
         assert_eq!(
             Ok(JSONValue::Object(map)),
@@ -248,20 +453,252 @@ mod test {
 
     #[test]
     fn value_object_empty() {
-        assert_eq!(Ok(JSONValue::Object(HashMap::new())), Parser::parse("{}"));
+        assert_eq!(
+            Ok(JSONValue::Object(JSONObject::new())),
+            Parser::parse("{}")
+        );
     }
 
     #[test]
     fn value_object_nested() {
-        let mut inner_map: HashMap<String, JSONValue> = HashMap::new();
-        inner_map.insert("b".to_string(), JSONValue::Number(SignedNum64::Integer(0)));
+        let mut inner_map = JSONObject::new();
+        inner_map.insert("b", JSONValue::Number(SignedNum64::Integer(0)));
 
-        let mut map: HashMap<String, JSONValue> = HashMap::new();
-        map.insert("a".to_string(), JSONValue::Object(inner_map));
+        let mut map = JSONObject::new();
+        map.insert("a", JSONValue::Object(inner_map));
 
         assert_eq!(
             Ok(JSONValue::Object(map)),
             Parser::parse("{\"a\": {\"b\": 0}}")
         );
     }
+
+    #[test]
+    fn string_invalid_escape() {
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::InvalidEscape,
+                7
+            )),
+            Parser::parse("[\"a\", \"\\x\"]")
+        );
+    }
+
+    #[test]
+    fn string_truncated_unicode_escape() {
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::InvalidEscape,
+                2
+            )),
+            Parser::parse("\"a\\u12\"")
+        );
+    }
+
+    #[test]
+    fn string_control_character() {
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::ControlCharacterInString,
+                3
+            )),
+            Parser::parse("\"ab\nc\"")
+        );
+    }
+
+    #[test]
+    fn string_lenient() {
+        let options = ParserOptions {
+            string_validation: StringValidation::Lenient,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Ok(JSONValue::Array(vec![
+                JSONValue::String("x".to_string()),
+                JSONValue::String("a?".to_string()),
+                JSONValue::String("a\tb".to_string()),
+            ])),
+            Parser::parse_with_options("[\"\\x\", \"a\\u12\", \"a\tb\"]", &options)
+        );
+    }
+
+    #[test]
+    fn string_preserved_lone_surrogate() {
+        let options = ParserOptions {
+            lone_surrogates: LoneSurrogates::Preserve,
+            ..Default::default()
+        };
+
+        let mut map = JSONObject::new();
+        map.insert(
+            "a",
+            JSONValue::Utf16String(Utf16String::from_units(vec![0xdc00])),
+        );
+
+        assert_eq!(
+            Ok(JSONValue::Object(map)),
+            Parser::parse_with_options("{\"a\": \"\\udc00\"}", &options)
+        );
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::LoneSurrogateInKey,
+                1
+            )),
+            Parser::parse_with_options("{\"\\ud800\": 1}", &options)
+        );
+    }
+
+    #[test]
+    fn key_transform() {
+        let options = ParserOptions {
+            key_transform: Some(KeyTransform::snake_case()),
+            ..Default::default()
+        };
+
+        let mut inner_map = JSONObject::new();
+        inner_map.insert("first_name", JSONValue::Null);
+
+        let mut map = JSONObject::new();
+        map.insert("user_info", JSONValue::Object(inner_map));
+
+        assert_eq!(
+            Ok(JSONValue::Object(map)),
+            Parser::parse_with_options("{\"userInfo\": {\"firstName\": null}}", &options)
+        );
+    }
+
+    #[test]
+    fn intern_keys() {
+        let options = ParserOptions {
+            intern_keys: true,
+            ..Default::default()
+        };
+        let value = Parser::parse_with_options("[{\"id\": 1}, {\"id\": 2}]", &options).unwrap();
+
+        let key = |i: usize| match value.get_as_array(i) {
+            Some(JSONValue::Object(obj)) => obj.keys().next().unwrap().clone(),
+            _ => panic!(),
+        };
+        assert!(Arc::ptr_eq(&key(0), &key(1)));
+    }
+
+    #[test]
+    fn borrowed() {
+        let text = "{\"name\": \"Bob\", \"quote\": \"a\\\"b\", \"tags\": [\"x\"]}";
+        let value = Parser::parse_borrowed(text).unwrap();
+
+        assert!(matches!(
+            value.get_as_object("name"),
+            Some(JSONValueRef::String(Cow::Borrowed("Bob")))
+        ));
+        assert!(matches!(
+            value.get_as_object("quote"),
+            Some(JSONValueRef::String(Cow::Owned(s))) if s == "a\"b"
+        ));
+        assert!(matches!(value.keys().next(), Some(Cow::Borrowed("name"))));
+        assert_eq!(Some("x"), value["tags"][0].as_str());
+        assert_eq!(Parser::parse(text), Ok(value.into_owned()));
+    }
+
+    #[test]
+    fn borrowed_duplicate_keys() {
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::DuplicateKey("a".to_string()),
+                9
+            )),
+            Parser::parse_borrowed("{\"a\": 1, \"a\": 2}")
+        );
+    }
+
+    #[test]
+    fn unexpected_token_position() {
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::UnexpectedToken,
+                4
+            )),
+            Parser::parse("[1, , 2]")
+        );
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::UnexpectedEOF,
+                6
+            )),
+            Parser::parse("{\"a\": ")
+        );
+        for text in ["[1, @]", "[1, tru]", "[1, -]"] {
+            assert_eq!(
+                Err(ParserError::with_position(
+                    ParserErrorKind::UnexpectedToken,
+                    4
+                )),
+                Parser::parse(text),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::UnexpectedEOF,
+                5
+            )),
+            Parser::parse("[\"ab\\")
+        );
+    }
+
+    fn parse_duplicates(policy: DuplicateKeys) -> Result<JSONValue, ParserError> {
+        let options = ParserOptions {
+            duplicate_keys: policy,
+            ..Default::default()
+        };
+        Parser::parse_with_options("{\"a\": 1, \"b\": [], \"a\": 2, \"a\": 3}", &options)
+    }
+
+    #[test]
+    fn duplicate_keys_error() {
+        assert_eq!(
+            Err(ParserError::with_position(
+                ParserErrorKind::DuplicateKey("a".to_string()),
+                18
+            )),
+            parse_duplicates(DuplicateKeys::Error)
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_first_and_last_wins() {
+        let mut map = JSONObject::new();
+        map.insert("a", JSONValue::Number(SignedNum64::Integer(1)));
+        map.insert("b", JSONValue::Array(vec![]));
+        assert_eq!(
+            Ok(JSONValue::Object(map.clone())),
+            parse_duplicates(DuplicateKeys::FirstWins)
+        );
+
+        map.insert("a", JSONValue::Number(SignedNum64::Integer(3)));
+        assert_eq!(
+            Ok(JSONValue::Object(map)),
+            parse_duplicates(DuplicateKeys::LastWins)
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_collect_into_array() {
+        let mut map = JSONObject::new();
+        map.insert(
+            "a",
+            JSONValue::Array(vec![
+                JSONValue::Number(SignedNum64::Integer(1)),
+                JSONValue::Number(SignedNum64::Integer(2)),
+                JSONValue::Number(SignedNum64::Integer(3)),
+            ]),
+        );
+        map.insert("b", JSONValue::Array(vec![]));
+        assert_eq!(
+            Ok(JSONValue::Object(map)),
+            parse_duplicates(DuplicateKeys::CollectIntoArray)
+        );
+    }
 }
@@ -4,22 +4,51 @@ use std::fmt;
 pub enum ParserErrorKind {
     UnexpectedToken,
     UnexpectedEOF,
+    InvalidEscape,
+    ControlCharacterInString,
+    DuplicateKey(String),
+    /// An object key holds an unpaired surrogate under `LoneSurrogates::Preserve`, where keys
+    /// cannot keep it.
+    LoneSurrogateInKey,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ParserError {
     kind: ParserErrorKind,
+    position: Option<usize>,
 }
 
 impl ParserError {
     pub fn new(kind: ParserErrorKind) -> Self {
-        ParserError { kind }
+        ParserError {
+            kind,
+            position: None,
+        }
+    }
+
+    /// Creates an error located at `position`, a byte offset into the input text.
+    pub fn with_position(kind: ParserErrorKind, position: usize) -> Self {
+        ParserError {
+            kind,
+            position: Some(position),
+        }
+    }
+
+    pub fn kind(&self) -> &ParserErrorKind {
+        &self.kind
+    }
+
+    pub fn position(&self) -> Option<usize> {
+        self.position
     }
 }
 
 impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.kind)
+        match self.position {
+            Some(position) => write!(f, "{:?} at position {}", self.kind, position),
+            None => write!(f, "{:?}", self.kind),
+        }
     }
 }
 
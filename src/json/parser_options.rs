@@ -0,0 +1,51 @@
+use super::key_transform::KeyTransform;
+
+/// How string literals are checked against RFC 8259.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum StringValidation {
+    /// Rejects unknown or truncated escape sequences and raw control characters.
+    #[default]
+    Strict,
+    /// Accepts malformed strings from legacy producers: an unknown escape `\x` becomes `x`,
+    /// a malformed `\u` escape becomes `?` and control characters are kept as they are.
+    Lenient,
+}
+
+/// What to do with `\uXXXX` escapes that encode an unpaired UTF-16 surrogate.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum LoneSurrogates {
+    /// Replaces each unpaired surrogate with U+FFFD.
+    #[default]
+    Replace,
+    /// Keeps unpaired surrogates by parsing the affected string values into
+    /// `JSONValue::Utf16String`, so that they survive serialization unchanged. Object keys
+    /// cannot hold them, so such a key fails with `ParserErrorKind::LoneSurrogateInKey`.
+    Preserve,
+}
+
+/// What to do when an object contains the same key more than once. Parsers disagreeing on this
+/// is a known source of request smuggling, so the default rejects such documents.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum DuplicateKeys {
+    /// Fails with `ParserErrorKind::DuplicateKey` at the position of the repeated key.
+    #[default]
+    Error,
+    /// Keeps the value of the first occurrence.
+    FirstWins,
+    /// Keeps the value of the last occurrence.
+    LastWins,
+    /// Gathers the values of all occurrences into an array, in document order.
+    CollectIntoArray,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ParserOptions {
+    pub string_validation: StringValidation,
+    pub lone_surrogates: LoneSurrogates,
+    /// Applied to every object key before it is inserted.
+    pub key_transform: Option<KeyTransform>,
+    /// Shares one allocation between all equal object keys in the document, which saves memory
+    /// on large arrays of records with the same fields.
+    pub intern_keys: bool,
+    pub duplicate_keys: DuplicateKeys,
+}
@@ -0,0 +1,419 @@
+use std::fmt;
+
+use super::json_value::JSONValue;
+use super::object::JSONObject;
+use super::ordering::compare;
+use super::pointer::{JsonPointer, PointerError};
+
+/// One operation of an RFC 6902 JSON Patch.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PatchOperation {
+    Add {
+        path: JsonPointer,
+        value: JSONValue,
+    },
+    Remove {
+        path: JsonPointer,
+    },
+    Replace {
+        path: JsonPointer,
+        value: JSONValue,
+    },
+    Move {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    Copy {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    /// Fails unless the value at `path` equals `value`, comparing numbers by value and objects
+    /// regardless of member order.
+    Test {
+        path: JsonPointer,
+        value: JSONValue,
+    },
+}
+
+impl PatchOperation {
+    /// The `op` name, e.g. `"add"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatchOperation::Add { .. } => "add",
+            PatchOperation::Remove { .. } => "remove",
+            PatchOperation::Replace { .. } => "replace",
+            PatchOperation::Move { .. } => "move",
+            PatchOperation::Copy { .. } => "copy",
+            PatchOperation::Test { .. } => "test",
+        }
+    }
+
+    fn apply(&self, target: &mut JSONValue) -> Result<(), PatchErrorKind> {
+        match self {
+            PatchOperation::Add { path, value } => {
+                path.insert(target, value.clone())?;
+            }
+            PatchOperation::Remove { path } => {
+                path.remove(target)?;
+            }
+            PatchOperation::Replace { path, value } => {
+                path.replace(target, value.clone())?;
+            }
+            PatchOperation::Move { from, path } => {
+                if from == path {
+                    from.get(target)?;
+                    return Ok(());
+                }
+                if path.tokens().starts_with(from.tokens()) {
+                    return Err(PatchErrorKind::MoveIntoChild);
+                }
+                let value = from.remove(target)?;
+                path.insert(target, value)?;
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = from.get(target)?.clone();
+                path.insert(target, value)?;
+            }
+            PatchOperation::Test { path, value } => {
+                if compare(path.get(target)?, value).is_ne() {
+                    return Err(PatchErrorKind::TestFailed);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PatchErrorKind {
+    /// The patch document is not an array.
+    NotAnArray,
+    /// The operation is not an object, has an unknown `op`, or lacks a member it requires.
+    InvalidOperation(String),
+    /// A pointer is malformed, or does not resolve when the operation is applied.
+    Pointer(PointerError),
+    /// A `move` whose `path` is inside its `from`.
+    MoveIntoChild,
+    TestFailed,
+}
+
+impl From<PointerError> for PatchErrorKind {
+    fn from(error: PointerError) -> Self {
+        PatchErrorKind::Pointer(error)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PatchError {
+    index: Option<usize>,
+    kind: PatchErrorKind,
+}
+
+impl PatchError {
+    /// The index of the failing operation in the patch, or `None` if the patch document itself
+    /// is not an array.
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn kind(&self) -> &PatchErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(index) = self.index {
+            write!(f, "operation {}: ", index)?;
+        }
+        match &self.kind {
+            PatchErrorKind::NotAnArray => write!(f, "patch must be an array of operations"),
+            PatchErrorKind::InvalidOperation(message) => write!(f, "{}", message),
+            PatchErrorKind::Pointer(error) => write!(f, "{}", error),
+            PatchErrorKind::MoveIntoChild => {
+                write!(f, "cannot move a value into one of its children")
+            }
+            PatchErrorKind::TestFailed => write!(f, "test failed"),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// An RFC 6902 JSON Patch: a list of operations applied in order.
+///
+/// ```
+/// use rust_json::json;
+/// use rust_json::json::patch::Patch;
+///
+/// let patch = Patch::from_json(&json!([
+///     {"op": "test", "path": "/version", "value": 1},
+///     {"op": "replace", "path": "/version", "value": 2},
+///     {"op": "add", "path": "/tags/-", "value": "new"}
+/// ]))
+/// .unwrap();
+/// let mut doc = json!({"version": 1, "tags": []});
+/// patch.apply(&mut doc).unwrap();
+/// assert_eq!(json!({"version": 2, "tags": ["new"]}), doc);
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Patch(Vec<PatchOperation>);
+
+impl Patch {
+    pub fn new(operations: Vec<PatchOperation>) -> Self {
+        Self(operations)
+    }
+
+    pub fn operations(&self) -> &[PatchOperation] {
+        &self.0
+    }
+
+    /// Reads a patch document. Members other than those the operation uses are ignored.
+    pub fn from_json(value: &JSONValue) -> Result<Self, PatchError> {
+        let JSONValue::Array(operations) = value else {
+            return Err(PatchError {
+                index: None,
+                kind: PatchErrorKind::NotAnArray,
+            });
+        };
+        operations
+            .iter()
+            .enumerate()
+            .map(|(index, operation)| {
+                parse_operation(operation).map_err(|kind| PatchError {
+                    index: Some(index),
+                    kind,
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn to_json(&self) -> JSONValue {
+        let operations = self.0.iter().map(|operation| {
+            let mut object = JSONObject::new();
+            object.insert("op", JSONValue::String(operation.name().to_string()));
+            match operation {
+                PatchOperation::Add { path, value }
+                | PatchOperation::Replace { path, value }
+                | PatchOperation::Test { path, value } => {
+                    object.insert("path", JSONValue::String(path.to_string()));
+                    object.insert("value", value.clone());
+                }
+                PatchOperation::Remove { path } => {
+                    object.insert("path", JSONValue::String(path.to_string()));
+                }
+                PatchOperation::Move { from, path } | PatchOperation::Copy { from, path } => {
+                    object.insert("from", JSONValue::String(from.to_string()));
+                    object.insert("path", JSONValue::String(path.to_string()));
+                }
+            }
+            JSONValue::Object(object)
+        });
+        JSONValue::Array(operations.collect())
+    }
+
+    /// Applies the operations in order. If one fails, `target` is left unchanged and the error
+    /// names the operation's index.
+    pub fn apply(&self, target: &mut JSONValue) -> Result<(), PatchError> {
+        let mut patched = target.clone();
+        for (index, operation) in self.0.iter().enumerate() {
+            operation.apply(&mut patched).map_err(|kind| PatchError {
+                index: Some(index),
+                kind,
+            })?;
+        }
+        *target = patched;
+        Ok(())
+    }
+}
+
+fn parse_operation(value: &JSONValue) -> Result<PatchOperation, PatchErrorKind> {
+    let invalid = |message: String| PatchErrorKind::InvalidOperation(message);
+    let JSONValue::Object(object) = value else {
+        return Err(invalid(format!(
+            "expected an object, found {}",
+            value.type_name()
+        )));
+    };
+    let member = |name: &str| {
+        object
+            .get(name)
+            .ok_or_else(|| invalid(format!("missing {:?}", name)))
+    };
+    let pointer = |name: &str| -> Result<JsonPointer, PatchErrorKind> {
+        let text = member(name)?
+            .as_str()
+            .ok_or_else(|| invalid(format!("{:?} must be a string", name)))?;
+        Ok(JsonPointer::parse(text)?)
+    };
+
+    let op = member("op")?
+        .as_str()
+        .ok_or_else(|| invalid("\"op\" must be a string".to_string()))?;
+    let operation = match op {
+        "add" => PatchOperation::Add {
+            path: pointer("path")?,
+            value: member("value")?.clone(),
+        },
+        "remove" => PatchOperation::Remove {
+            path: pointer("path")?,
+        },
+        "replace" => PatchOperation::Replace {
+            path: pointer("path")?,
+            value: member("value")?.clone(),
+        },
+        "move" => PatchOperation::Move {
+            from: pointer("from")?,
+            path: pointer("path")?,
+        },
+        "copy" => PatchOperation::Copy {
+            from: pointer("from")?,
+            path: pointer("path")?,
+        },
+        "test" => PatchOperation::Test {
+            path: pointer("path")?,
+            value: member("value")?.clone(),
+        },
+        op => return Err(invalid(format!("unknown op {:?}", op))),
+    };
+    Ok(operation)
+}
+
+impl JSONValue {
+    /// Reads `patch` as a JSON Patch document and applies it atomically; see `Patch`.
+    pub fn patch(&mut self, patch: &JSONValue) -> Result<(), PatchError> {
+        Patch::from_json(patch)?.apply(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+    use crate::json::pointer::PointerErrorKind;
+
+    fn patched(mut doc: JSONValue, patch: JSONValue) -> JSONValue {
+        doc.patch(&patch).unwrap();
+        doc
+    }
+
+    #[test]
+    fn operations() {
+        assert_eq!(
+            json!({"a": 1, "b": [1, 2, 3]}),
+            patched(
+                json!({"a": 1, "b": [1, 3]}),
+                json!([{"op": "add", "path": "/b/1", "value": 2}])
+            )
+        );
+        assert_eq!(
+            json!({"b": [3]}),
+            patched(
+                json!({"a": 1, "b": [2, 3]}),
+                json!([
+                    {"op": "remove", "path": "/a"},
+                    {"op": "remove", "path": "/b/0"}
+                ])
+            )
+        );
+        assert_eq!(
+            json!({"a": {"x": true}}),
+            patched(
+                json!({"a": 1}),
+                json!([{"op": "replace", "path": "/a", "value": {"x": true}}])
+            )
+        );
+        assert_eq!(
+            json!({"b": {"c": 1}, "d": [1]}),
+            patched(
+                json!({"a": {"c": 1}, "d": []}),
+                json!([
+                    {"op": "move", "from": "/a", "path": "/b"},
+                    {"op": "copy", "from": "/b/c", "path": "/d/-"}
+                ])
+            )
+        );
+        assert_eq!(
+            json!({"a": [1, 2]}),
+            patched(
+                json!({"a": [1, 2]}),
+                json!([
+                    {"op": "test", "path": "/a", "value": [1, 2.0]},
+                    {"op": "move", "from": "/a", "path": "/a"}
+                ])
+            )
+        );
+        assert_eq!(
+            json!([1]),
+            patched(json!({}), json!([{"op": "add", "path": "", "value": [1]}]))
+        );
+    }
+
+    #[test]
+    fn test_compares_values() {
+        let mut doc = json!({"o": {"a": 1, "b": 2}});
+        assert!(doc
+            .patch(&json!([{"op": "test", "path": "/o", "value": {"b": 2, "a": 1.0}}]))
+            .is_ok());
+        let error = doc
+            .patch(&json!([{"op": "test", "path": "/o/a", "value": "1"}]))
+            .unwrap_err();
+        assert_eq!(&PatchErrorKind::TestFailed, error.kind());
+    }
+
+    #[test]
+    fn atomic() {
+        let mut doc = json!({"a": 1, "b": [1]});
+        let error = doc
+            .patch(&json!([
+                {"op": "replace", "path": "/a", "value": 2},
+                {"op": "add", "path": "/b/-", "value": 2},
+                {"op": "remove", "path": "/missing"}
+            ]))
+            .unwrap_err();
+        assert_eq!(Some(2), error.index());
+        assert!(matches!(
+            error.kind(),
+            PatchErrorKind::Pointer(e) if *e.kind() == PointerErrorKind::NotFound
+        ));
+        assert_eq!(json!({"a": 1, "b": [1]}), doc);
+        assert_eq!(
+            "operation 2: \"/missing\": no such value",
+            error.to_string()
+        );
+
+        let error = doc
+            .patch(&json!([{"op": "move", "from": "/b", "path": "/b/0"}]))
+            .unwrap_err();
+        assert_eq!(&PatchErrorKind::MoveIntoChild, error.kind());
+    }
+
+    #[test]
+    fn parse() {
+        let error = |patch: JSONValue| Patch::from_json(&patch).unwrap_err();
+        assert_eq!(&PatchErrorKind::NotAnArray, error(json!({})).kind());
+        assert_eq!(None, error(json!({})).index());
+
+        let missing = error(json!([{"op": "remove", "path": "/a"}, {"op": "add", "path": "/a"}]));
+        assert_eq!(Some(1), missing.index());
+        assert_eq!("operation 1: missing \"value\"", missing.to_string());
+        assert_eq!(
+            "operation 0: unknown op \"delete\"",
+            error(json!([{"op": "delete", "path": "/a"}])).to_string()
+        );
+        assert!(matches!(
+            error(json!([{"op": "remove", "path": "a"}])).kind(),
+            PatchErrorKind::Pointer(_)
+        ));
+
+        let patch = json!([
+            {"op": "add", "path": "/a~1b", "value": null},
+            {"op": "copy", "from": "/a", "path": "/c"},
+            {"op": "remove", "path": "/c"}
+        ]);
+        let parsed = Patch::from_json(&patch).unwrap();
+        assert_eq!("add", parsed.operations()[0].name());
+        assert_eq!(patch, parsed.to_json());
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+
+use super::json_value::Key;
+use super::pointer::JsonPointer;
+
+/// One step from a value to a child: an object key or an array index.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum PathSegment {
+    Key(Key),
+    Index(usize),
+}
+
+impl From<&str> for PathSegment {
+    fn from(key: &str) -> Self {
+        PathSegment::Key(key.into())
+    }
+}
+
+impl From<Key> for PathSegment {
+    fn from(key: Key) -> Self {
+        PathSegment::Key(key)
+    }
+}
+
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+/// The location of a value within a document, as a list of segments from the root.
+///
+/// Displays as a JSONPath expression such as `$.users[0].name`; keys that are not plain
+/// identifiers are written in bracket notation, e.g. `$['first name']`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Path(Vec<PathSegment>);
+
+impl Path {
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, segment: impl Into<PathSegment>) {
+        self.0.push(segment.into());
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.0.pop()
+    }
+
+    /// Renders the path as an RFC 6901 JSON Pointer such as `/users/0/name`, escaping `~` as
+    /// `~0` and `/` as `~1` within keys. The root is the empty string.
+    pub fn to_pointer(&self) -> String {
+        JsonPointer::from(self).to_string()
+    }
+
+    /// Renders the path as an RFC 9535 normalized path such as `$['users'][0]['name']`, which
+    /// uses bracket notation throughout so that equal locations always give equal strings.
+    pub fn to_normalized(&self) -> String {
+        let mut normalized = String::from("$");
+        for segment in &self.0 {
+            match segment {
+                PathSegment::Key(key) => {
+                    normalized.push('[');
+                    write_quoted_key(&mut normalized, key).unwrap();
+                    normalized.push(']');
+                }
+                PathSegment::Index(index) => normalized.push_str(&format!("[{}]", index)),
+            }
+        }
+        normalized
+    }
+
+    /// Returns a copy of this path extended by `segment`.
+    pub fn join(&self, segment: impl Into<PathSegment>) -> Self {
+        let mut path = self.clone();
+        path.push(segment);
+        path
+    }
+}
+
+impl From<Vec<PathSegment>> for Path {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self(segments)
+    }
+}
+
+impl From<Path> for Vec<PathSegment> {
+    fn from(path: Path) -> Self {
+        path.0
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Writes `key` as a single-quoted JSONPath string literal, escaped as in normalized paths.
+pub(crate) fn write_quoted_key(f: &mut impl fmt::Write, key: &str) -> fmt::Result {
+    f.write_char('\'')?;
+    for c in key.chars() {
+        match c {
+            '\'' => f.write_str("\\'")?,
+            '\\' => f.write_str("\\\\")?,
+            '\u{8}' => f.write_str("\\b")?,
+            '\u{c}' => f.write_str("\\f")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c < '\x20' => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('\'')
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("$")?;
+        for segment in &self.0 {
+            match segment {
+                PathSegment::Key(key) if is_identifier(key) => write!(f, ".{}", key)?,
+                PathSegment::Key(key) => {
+                    f.write_str("[")?;
+                    write_quoted_key(f, key)?;
+                    f.write_str("]")?;
+                }
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!("$", Path::root().to_string());
+
+        let path = Path::from(vec!["users".into(), 0.into(), "first name".into()]);
+        assert_eq!("$.users[0]['first name']", path.to_string());
+
+        let path = Path::root().join("it's").join("_a1");
+        assert_eq!("$['it\\'s']._a1", path.to_string());
+    }
+
+    #[test]
+    fn normalized() {
+        assert_eq!("$", Path::root().to_normalized());
+
+        let path = Path::root().join("a").join(1).join("'\n\u{1}");
+        assert_eq!("$['a'][1]['\\'\\n\\u0001']", path.to_normalized());
+    }
+
+    #[test]
+    fn pointer() {
+        assert_eq!("", Path::root().to_pointer());
+
+        let path = Path::from(vec!["a/b".into(), 0.into(), "m~n".into(), "".into()]);
+        assert_eq!("/a~1b/0/m~0n/", path.to_pointer());
+    }
+}
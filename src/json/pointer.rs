@@ -0,0 +1,444 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::json_value::JSONValue;
+use super::path::{Path, PathSegment};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PointerErrorKind {
+    /// The pointer is not empty and does not start with `/`.
+    MissingSlash,
+    /// A `~` is not followed by `0` or `1`.
+    InvalidEscape,
+    /// The object has no member with the token as key, or the array no element at the index.
+    NotFound,
+    /// The token is not an array index: not a number, a number with leading zeros, or `-` where
+    /// an existing element is required.
+    InvalidIndex,
+    /// The value the token is applied to is neither an object nor an array.
+    NotAContainer,
+    /// The operation cannot remove the whole document.
+    RemoveRoot,
+}
+
+/// Explains why a pointer is invalid or could not be applied. `pointer` is the whole pointer for
+/// syntax errors, and otherwise the prefix up to and including the token that failed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PointerError {
+    pointer: String,
+    kind: PointerErrorKind,
+}
+
+impl PointerError {
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    pub fn kind(&self) -> &PointerErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self.kind {
+            PointerErrorKind::MissingSlash => "pointer must be empty or start with '/'",
+            PointerErrorKind::InvalidEscape => "'~' must be followed by '0' or '1'",
+            PointerErrorKind::NotFound => "no such value",
+            PointerErrorKind::InvalidIndex => "not a valid array index",
+            PointerErrorKind::NotAContainer => "not an object or array",
+            PointerErrorKind::RemoveRoot => "cannot remove the root",
+        };
+        write!(f, "{:?}: {}", self.pointer, message)
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+/// A parsed RFC 6901 JSON Pointer such as `/users/0/name`: a list of reference tokens, with
+/// `~0` and `~1` already unescaped to `~` and `/`. The empty pointer refers to the whole
+/// document.
+///
+/// ```
+/// use rust_json::json;
+/// use rust_json::json::pointer::JsonPointer;
+///
+/// let mut value = json!({"users": [{"name": "Alice"}], "a/b": 1});
+/// assert_eq!(Some(&json!("Alice")), value.pointer("/users/0/name"));
+/// assert_eq!(Some(&json!(1)), value.pointer("/a~1b"));
+///
+/// let pointer: JsonPointer = "/users/-".parse().unwrap();
+/// pointer.insert(&mut value, json!({"name": "Bob"})).unwrap();
+/// assert_eq!(Some(&json!("Bob")), value.pointer("/users/1/name"));
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct JsonPointer(Vec<String>);
+
+impl JsonPointer {
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(pointer: &str) -> Result<Self, PointerError> {
+        let error = |kind| PointerError {
+            pointer: pointer.to_string(),
+            kind,
+        };
+        if pointer.is_empty() {
+            return Ok(Self::root());
+        }
+        let rest = pointer
+            .strip_prefix('/')
+            .ok_or_else(|| error(PointerErrorKind::MissingSlash))?;
+
+        rest.split('/')
+            .map(|token| unescape(token).ok_or_else(|| error(PointerErrorKind::InvalidEscape)))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.0
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, token: impl Into<String>) {
+        self.0.push(token.into());
+    }
+
+    /// Returns the pointer to the parent and the last token, or `None` for the root.
+    pub fn split_last(&self) -> Option<(JsonPointer, &str)> {
+        self.0
+            .split_last()
+            .map(|(last, parent)| (JsonPointer(parent.to_vec()), last.as_str()))
+    }
+
+    fn error(&self, depth: usize, kind: PointerErrorKind) -> PointerError {
+        PointerError {
+            pointer: JsonPointer(self.0[..depth].to_vec()).to_string(),
+            kind,
+        }
+    }
+
+    pub fn get<'a>(&self, value: &'a JSONValue) -> Result<&'a JSONValue, PointerError> {
+        let mut current = value;
+        for (depth, token) in self.0.iter().enumerate() {
+            let found = match current {
+                JSONValue::Object(obj) => obj.get(token),
+                JSONValue::Array(arr) => {
+                    let index = parse_index(token, arr.len(), false)
+                        .ok_or_else(|| self.error(depth + 1, PointerErrorKind::InvalidIndex))?;
+                    arr.get(index)
+                }
+                _ => return Err(self.error(depth + 1, PointerErrorKind::NotAContainer)),
+            };
+            current = found.ok_or_else(|| self.error(depth + 1, PointerErrorKind::NotFound))?;
+        }
+        Ok(current)
+    }
+
+    pub fn get_mut<'a>(&self, value: &'a mut JSONValue) -> Result<&'a mut JSONValue, PointerError> {
+        self.get_mut_prefix(value, self.0.len())
+    }
+
+    fn get_mut_prefix<'a>(
+        &self,
+        value: &'a mut JSONValue,
+        len: usize,
+    ) -> Result<&'a mut JSONValue, PointerError> {
+        let mut current = value;
+        for (depth, token) in self.0[..len].iter().enumerate() {
+            let found = match current {
+                JSONValue::Object(obj) => obj.get_mut(token),
+                JSONValue::Array(arr) => {
+                    let index = parse_index(token, arr.len(), false)
+                        .ok_or_else(|| self.error(depth + 1, PointerErrorKind::InvalidIndex))?;
+                    arr.get_mut(index)
+                }
+                _ => return Err(self.error(depth + 1, PointerErrorKind::NotAContainer)),
+            };
+            current = found.ok_or_else(|| self.error(depth + 1, PointerErrorKind::NotFound))?;
+        }
+        Ok(current)
+    }
+
+    /// Adds `value` as in the JSON Patch `add` operation: sets the object member, inserts into
+    /// the array before the index, shifting later elements, or appends for `-`. The parent must
+    /// exist. Returns the value previously stored under an object key, or the whole document
+    /// for the root pointer.
+    pub fn insert(
+        &self,
+        target: &mut JSONValue,
+        value: JSONValue,
+    ) -> Result<Option<JSONValue>, PointerError> {
+        let Some(last) = self.0.last() else {
+            return Ok(Some(std::mem::replace(target, value)));
+        };
+        let depth = self.0.len();
+        match self.get_mut_prefix(target, depth - 1)? {
+            JSONValue::Object(obj) => Ok(obj.insert(last.as_str(), value)),
+            JSONValue::Array(arr) => {
+                let index = parse_index(last, arr.len(), true)
+                    .filter(|index| *index <= arr.len())
+                    .ok_or_else(|| self.error(depth, PointerErrorKind::InvalidIndex))?;
+                arr.insert(index, value);
+                Ok(None)
+            }
+            _ => Err(self.error(depth, PointerErrorKind::NotAContainer)),
+        }
+    }
+
+    /// Removes and returns the value, shifting later array elements down.
+    pub fn remove(&self, target: &mut JSONValue) -> Result<JSONValue, PointerError> {
+        let Some(last) = self.0.last() else {
+            return Err(self.error(0, PointerErrorKind::RemoveRoot));
+        };
+        let depth = self.0.len();
+        match self.get_mut_prefix(target, depth - 1)? {
+            JSONValue::Object(obj) => obj
+                .remove(last)
+                .ok_or_else(|| self.error(depth, PointerErrorKind::NotFound)),
+            JSONValue::Array(arr) => {
+                let index = parse_index(last, arr.len(), false)
+                    .ok_or_else(|| self.error(depth, PointerErrorKind::InvalidIndex))?;
+                if index < arr.len() {
+                    Ok(arr.remove(index))
+                } else {
+                    Err(self.error(depth, PointerErrorKind::NotFound))
+                }
+            }
+            _ => Err(self.error(depth, PointerErrorKind::NotAContainer)),
+        }
+    }
+
+    /// Replaces an existing value and returns the old one.
+    pub fn replace(
+        &self,
+        target: &mut JSONValue,
+        value: JSONValue,
+    ) -> Result<JSONValue, PointerError> {
+        self.get_mut(target)
+            .map(|slot| std::mem::replace(slot, value))
+    }
+}
+
+/// Parses an array index token: `0` or a number without leading zeros, or `-` for `len` when
+/// `allow_end` is set. Bounds are left to the caller.
+fn parse_index(token: &str, len: usize, allow_end: bool) -> Option<usize> {
+    match token {
+        "-" if allow_end => Some(len),
+        "0" => Some(0),
+        _ if token.starts_with('0') || !token.bytes().all(|b| b.is_ascii_digit()) => None,
+        _ => token.parse().ok(),
+    }
+}
+
+fn unescape(token: &str) -> Option<String> {
+    let mut result = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => result.push('~'),
+                Some('1') => result.push('/'),
+                _ => return None,
+            },
+            c => result.push(c),
+        }
+    }
+    Some(result)
+}
+
+impl FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in &self.0 {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&Path> for JsonPointer {
+    fn from(path: &Path) -> Self {
+        Self(
+            path.segments()
+                .iter()
+                .map(|segment| match segment {
+                    PathSegment::Key(key) => key.to_string(),
+                    PathSegment::Index(index) => index.to_string(),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl JSONValue {
+    /// Looks up a value by an RFC 6901 JSON Pointer. Returns `None` if the pointer is invalid or
+    /// does not resolve; use `JsonPointer::get` to learn why.
+    pub fn pointer(&self, pointer: &str) -> Option<&JSONValue> {
+        JsonPointer::parse(pointer).ok()?.get(self).ok()
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JSONValue> {
+        JsonPointer::parse(pointer).ok()?.get_mut(self).ok()
+    }
+
+    /// Parses `pointer` and applies `JsonPointer::insert`.
+    pub fn pointer_insert(
+        &mut self,
+        pointer: &str,
+        value: JSONValue,
+    ) -> Result<Option<JSONValue>, PointerError> {
+        JsonPointer::parse(pointer)?.insert(self, value)
+    }
+
+    /// Parses `pointer` and applies `JsonPointer::remove`.
+    pub fn pointer_remove(&mut self, pointer: &str) -> Result<JSONValue, PointerError> {
+        JsonPointer::parse(pointer)?.remove(self)
+    }
+
+    /// Parses `pointer` and applies `JsonPointer::replace`.
+    pub fn pointer_replace(
+        &mut self,
+        pointer: &str,
+        value: JSONValue,
+    ) -> Result<JSONValue, PointerError> {
+        JsonPointer::parse(pointer)?.replace(self, value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn parse() {
+        let pointer = JsonPointer::parse("/a~1b/~0/0/").unwrap();
+        assert_eq!(["a/b", "~", "0", ""], pointer.tokens());
+        assert_eq!("/a~1b/~0/0/", pointer.to_string());
+        assert!(JsonPointer::parse("").unwrap().is_root());
+
+        assert_eq!(
+            PointerErrorKind::MissingSlash,
+            *JsonPointer::parse("a").unwrap_err().kind()
+        );
+        let error = JsonPointer::parse("/a~2").unwrap_err();
+        assert_eq!(PointerErrorKind::InvalidEscape, *error.kind());
+        assert_eq!(
+            "\"/a~2\": '~' must be followed by '0' or '1'",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn rfc_examples() {
+        let value = json!({
+            "foo": ["bar", "baz"], "": 0, "a/b": 1, "c%d": 2, "e^f": 3,
+            "g|h": 4, "i\\j": 5, "k\"l": 6, " ": 7, "m~n": 8
+        });
+        let cases = [
+            ("", value.clone()),
+            ("/foo", json!(["bar", "baz"])),
+            ("/foo/0", json!("bar")),
+            ("/", json!(0)),
+            ("/a~1b", json!(1)),
+            ("/c%d", json!(2)),
+            ("/e^f", json!(3)),
+            ("/g|h", json!(4)),
+            ("/i\\j", json!(5)),
+            ("/k\"l", json!(6)),
+            ("/ ", json!(7)),
+            ("/m~0n", json!(8)),
+        ];
+        for (pointer, expected) in cases {
+            assert_eq!(Some(&expected), value.pointer(pointer), "{}", pointer);
+        }
+    }
+
+    #[test]
+    fn lookup_errors() {
+        let value = json!({"a": [1, {"b": true}]});
+        let kind = |pointer: &str| {
+            JsonPointer::parse(pointer)
+                .unwrap()
+                .get(&value)
+                .unwrap_err()
+                .kind()
+                .clone()
+        };
+        assert_eq!(PointerErrorKind::NotFound, kind("/x"));
+        assert_eq!(PointerErrorKind::NotFound, kind("/a/2"));
+        assert_eq!(PointerErrorKind::InvalidIndex, kind("/a/01"));
+        assert_eq!(PointerErrorKind::InvalidIndex, kind("/a/-"));
+        assert_eq!(PointerErrorKind::NotAContainer, kind("/a/0/b"));
+
+        let error = JsonPointer::parse("/a/1/c/d")
+            .unwrap()
+            .get(&value)
+            .unwrap_err();
+        assert_eq!("/a/1/c", error.pointer());
+    }
+
+    #[test]
+    fn mutation() {
+        let mut value = json!({"list": [1, 3]});
+
+        *value.pointer_mut("/list/0").unwrap() = json!(0);
+        assert_eq!(Ok(None), value.pointer_insert("/list/1", json!(2)));
+        assert_eq!(Ok(None), value.pointer_insert("/list/-", json!(4)));
+        assert_eq!(Ok(None), value.pointer_insert("/new", json!({})));
+        assert_eq!(Ok(None), value.pointer_insert("/new/a~1b", json!(true)));
+        assert_eq!(json!({"list": [0, 2, 3, 4], "new": {"a/b": true}}), value);
+
+        assert_eq!(Ok(json!(3)), value.pointer_remove("/list/2"));
+        assert_eq!(
+            Ok(json!(true)),
+            value.pointer_replace("/new/a~1b", json!(false))
+        );
+        assert_eq!(json!({"list": [0, 2, 4], "new": {"a/b": false}}), value);
+
+        fn kind<T: fmt::Debug>(result: Result<T, PointerError>) -> PointerErrorKind {
+            result.unwrap_err().kind().clone()
+        }
+        let mut copy = value.clone();
+        assert_eq!(
+            PointerErrorKind::InvalidIndex,
+            kind(copy.pointer_insert("/list/4", json!(0)))
+        );
+        assert_eq!(
+            PointerErrorKind::NotFound,
+            kind(copy.pointer_insert("/missing/a", json!(0)))
+        );
+        assert_eq!(
+            PointerErrorKind::NotFound,
+            kind(copy.pointer_remove("/list/3"))
+        );
+        assert_eq!(
+            PointerErrorKind::NotFound,
+            kind(copy.pointer_replace("/x", json!(0)))
+        );
+        assert_eq!(PointerErrorKind::RemoveRoot, kind(copy.pointer_remove("")));
+        assert_eq!(value, copy);
+
+        assert_eq!(Ok(Some(value)), copy.pointer_insert("", json!(1)));
+        assert_eq!(json!(1), copy);
+    }
+
+    #[test]
+    fn from_path() {
+        let path = Path::root().join("a/b").join(0);
+        assert_eq!("/a~1b/0", JsonPointer::from(&path).to_string());
+    }
+}
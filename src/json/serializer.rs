@@ -0,0 +1,147 @@
+use super::json_value::JSONValue;
+use super::string::{escape, escape_utf16};
+use super::util::signed_num_64::SignedNum64;
+
+pub struct Serializer {
+    output: String,
+}
+
+impl Serializer {
+    fn new() -> Self {
+        Self {
+            output: String::new(),
+        }
+    }
+
+    /// Serializes `value` as compact JSON text.
+    pub fn serialize(value: &JSONValue) -> String {
+        let mut serializer = Serializer::new();
+        serializer.serialize_value(value);
+        serializer.output
+    }
+
+    fn serialize_number(&mut self, num: &SignedNum64) {
+        match num {
+            SignedNum64::Integer(num) => self.output.push_str(&num.to_string()),
+            SignedNum64::Float(num) if !num.is_finite() => self.output.push_str("null"),
+            SignedNum64::Float(num) => {
+                let text = num.to_string();
+                let is_integral = !text.contains(['.', 'e', 'E']);
+                self.output.push_str(&text);
+                if is_integral {
+                    self.output.push_str(".0");
+                }
+            }
+        }
+    }
+
+    fn serialize_string(&mut self, s: &str) {
+        self.output.push('"');
+        self.output.push_str(&escape(s));
+        self.output.push('"');
+    }
+
+    fn serialize_value(&mut self, value: &JSONValue) {
+        match value {
+            JSONValue::True => self.output.push_str("true"),
+            JSONValue::False => self.output.push_str("false"),
+            JSONValue::Null => self.output.push_str("null"),
+            JSONValue::Number(num) => self.serialize_number(num),
+            JSONValue::String(s) => self.serialize_string(s),
+            JSONValue::Utf16String(s) => {
+                self.output.push('"');
+                self.output.push_str(&escape_utf16(s));
+                self.output.push('"');
+            }
+            JSONValue::Array(arr) => {
+                self.output.push('[');
+                for (i, val) in arr.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push(',');
+                    }
+                    self.serialize_value(val);
+                }
+                self.output.push(']');
+            }
+            JSONValue::Object(obj) => {
+                self.output.push('{');
+                for (i, (key, val)) in obj.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push(',');
+                    }
+                    self.serialize_string(key);
+                    self.output.push(':');
+                    self.serialize_value(val);
+                }
+                self.output.push('}');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parser::Parser;
+    use super::super::parser_options::{LoneSurrogates, ParserOptions};
+    use super::*;
+
+    #[test]
+    fn scalars() {
+        assert_eq!("true", Serializer::serialize(&JSONValue::True));
+        assert_eq!("false", Serializer::serialize(&JSONValue::False));
+        assert_eq!("null", Serializer::serialize(&JSONValue::Null));
+        assert_eq!(
+            "-12",
+            Serializer::serialize(&JSONValue::Number(SignedNum64::Integer(-12)))
+        );
+        assert_eq!(
+            "1.5",
+            Serializer::serialize(&JSONValue::Number(SignedNum64::Float(1.5)))
+        );
+        assert_eq!(
+            "3.0",
+            Serializer::serialize(&JSONValue::Number(SignedNum64::Float(3.0)))
+        );
+        assert_eq!(
+            "null",
+            Serializer::serialize(&JSONValue::Number(SignedNum64::Float(f64::NAN)))
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(
+            "\"a\\\"b\\\\c\\n\\u0001\"",
+            Serializer::serialize(&JSONValue::String("a\"b\\c\n\x01".to_string()))
+        );
+    }
+
+    #[test]
+    fn array() {
+        let value = Parser::parse("[1, [true, null], \"x\"]").unwrap();
+        assert_eq!("[1,[true,null],\"x\"]", Serializer::serialize(&value));
+    }
+
+    #[test]
+    fn object_keeps_key_order() {
+        let text = "{\"z\":1,\"a\":{\"y\":2,\"b\":3},\"m\":4}";
+        let value = Parser::parse(text).unwrap();
+        assert_eq!(text, Serializer::serialize(&value));
+    }
+
+    #[test]
+    fn round_trip_lone_surrogates() {
+        let options = ParserOptions {
+            lone_surrogates: LoneSurrogates::Preserve,
+            ..Default::default()
+        };
+        let value =
+            Parser::parse_with_options("[\"a\\ud83d\", \"\\udc00\\ud83d\\ude00\"]", &options)
+                .unwrap();
+
+        assert_eq!(
+            "[\"a\\ud83d\",\"\\udc00😀\"]",
+            Serializer::serialize(&value)
+        );
+    }
+}
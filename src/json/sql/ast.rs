@@ -0,0 +1,95 @@
+use super::super::json_path::JSONPath;
+use super::super::json_value::JSONValue;
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) struct Select {
+    /// The output columns, or `None` for `SELECT *`.
+    pub columns: Option<Vec<Column>>,
+    pub from: JSONPath,
+    pub filter: Option<Expr>,
+    pub group_by: Vec<Expr>,
+    pub having: Option<Expr>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) struct Column {
+    pub expr: Expr,
+    /// The alias, or the text of the expression.
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) struct OrderBy {
+    pub expr: Expr,
+    pub descending: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub(super) enum Expr {
+    Literal(JSONValue),
+    /// A possibly nested field of the row, e.g. `address.city`.
+    Column(Vec<String>),
+    /// An aggregate over the rows of a group; the argument is `None` for `count(*)`.
+    Aggregate(Aggregate, Option<Box<Expr>>),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    /// `expr IS NULL`, or `expr IS NOT NULL` if the flag is set.
+    IsNull(Box<Expr>, bool),
+}
+
+impl Expr {
+    pub fn has_aggregate(&self) -> bool {
+        match self {
+            Expr::Literal(_) | Expr::Column(_) => false,
+            Expr::Aggregate(..) => true,
+            Expr::Neg(expr) | Expr::Not(expr) | Expr::IsNull(expr, _) => expr.has_aggregate(),
+            Expr::And(left, right) | Expr::Or(left, right) | Expr::Binary(_, left, right) => {
+                left.has_aggregate() || right.has_aggregate()
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let aggregate = match name.to_ascii_lowercase().as_str() {
+            "count" => Aggregate::Count,
+            "sum" => Aggregate::Sum,
+            "avg" => Aggregate::Avg,
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            _ => return None,
+        };
+        Some(aggregate)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
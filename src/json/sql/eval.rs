@@ -0,0 +1,325 @@
+use std::cmp::Ordering;
+
+use super::super::json_value::JSONValue;
+use super::super::object::JSONObject;
+use super::super::ordering::{compare, compare_slices};
+use super::super::util::signed_num_64::{ArithmeticOp, SignedNum64};
+use super::ast::{Aggregate, BinOp, Expr, Select};
+use super::{SqlError, SqlErrorKind};
+
+fn type_error(message: String) -> SqlError {
+    SqlError::new(SqlErrorKind::TypeError(message))
+}
+
+fn boolean(value: bool) -> JSONValue {
+    if value {
+        JSONValue::True
+    } else {
+        JSONValue::False
+    }
+}
+
+/// Reads a value as an SQL boolean, where null is unknown.
+fn truth(value: &JSONValue) -> Result<Option<bool>, SqlError> {
+    match value {
+        JSONValue::True => Ok(Some(true)),
+        JSONValue::False => Ok(Some(false)),
+        JSONValue::Null => Ok(None),
+        value => Err(type_error(format!(
+            "expected a boolean, found {}",
+            value.type_name()
+        ))),
+    }
+}
+
+fn truth_value(value: Option<bool>) -> JSONValue {
+    value.map_or(JSONValue::Null, boolean)
+}
+
+pub(super) fn run(select: &Select, input: &JSONValue) -> Result<JSONValue, SqlError> {
+    let selected = select.from.query(input);
+    let mut rows: Vec<&JSONValue> = match selected.as_slice() {
+        [(_, JSONValue::Array(arr))] => arr.iter().collect(),
+        _ => selected.into_iter().map(|(_, value)| value).collect(),
+    };
+
+    if let Some(filter) = &select.filter {
+        let mut kept = Vec::new();
+        for row in rows {
+            if truth(&eval(filter, &[row])?)? == Some(true) {
+                kept.push(row);
+            }
+        }
+        rows = kept;
+    }
+
+    let aggregated = !select.group_by.is_empty()
+        || select.having.is_some()
+        || select
+            .columns
+            .iter()
+            .flatten()
+            .any(|c| c.expr.has_aggregate())
+        || select.order_by.iter().any(|o| o.expr.has_aggregate());
+    let mut groups = if !aggregated {
+        rows.into_iter().map(|row| vec![row]).collect()
+    } else if select.group_by.is_empty() {
+        vec![rows]
+    } else {
+        group(&select.group_by, rows)?
+    };
+
+    if let Some(having) = &select.having {
+        let mut kept = Vec::new();
+        for group in groups {
+            if truth(&eval(having, &group)?)? == Some(true) {
+                kept.push(group);
+            }
+        }
+        groups = kept;
+    }
+
+    let mut outputs = Vec::with_capacity(groups.len());
+    for group in &groups {
+        let output = match &select.columns {
+            None => group.first().map_or(JSONValue::Null, |row| (*row).clone()),
+            Some(columns) => {
+                let mut object = JSONObject::with_capacity(columns.len());
+                for column in columns {
+                    object.insert(column.name.as_str(), eval(&column.expr, group)?);
+                }
+                JSONValue::Object(object)
+            }
+        };
+
+        let mut keys = Vec::with_capacity(select.order_by.len());
+        for order in &select.order_by {
+            // A bare name refers to an output column before a field of the row.
+            let alias = match (&order.expr, &output) {
+                (Expr::Column(path), JSONValue::Object(object)) if path.len() == 1 => {
+                    object.get(&path[0])
+                }
+                _ => None,
+            };
+            keys.push(match alias {
+                Some(value) => value.clone(),
+                None => eval(&order.expr, group)?,
+            });
+        }
+        outputs.push((keys, output));
+    }
+
+    outputs.sort_by(|(l, _), (r, _)| {
+        l.iter()
+            .zip(r)
+            .zip(&select.order_by)
+            .map(|((l, r), order)| {
+                let ordering = compare(l, r);
+                if order.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let limit = select.limit.unwrap_or(usize::MAX);
+    Ok(JSONValue::Array(
+        outputs
+            .into_iter()
+            .skip(select.offset)
+            .take(limit)
+            .map(|(_, output)| output)
+            .collect(),
+    ))
+}
+
+/// Splits the rows into groups with equal keys, in the order each group first appears.
+fn group<'a>(keys: &[Expr], rows: Vec<&'a JSONValue>) -> Result<Vec<Vec<&'a JSONValue>>, SqlError> {
+    let mut groups: Vec<Vec<&JSONValue>> = Vec::new();
+    // The keys seen so far, sorted with the same order `=` uses so that `1` and `1.0` share a
+    // group, each with the index of its group.
+    let mut index: Vec<(Vec<JSONValue>, usize)> = Vec::new();
+    for row in rows {
+        let key = keys
+            .iter()
+            .map(|expr| eval(expr, &[row]))
+            .collect::<Result<Vec<_>, _>>()?;
+        match index.binary_search_by(|(seen, _)| compare_slices(seen, &key)) {
+            Ok(i) => groups[index[i].1].push(row),
+            Err(i) => {
+                index.insert(i, (key, groups.len()));
+                groups.push(vec![row]);
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Evaluates `expr` for a group of rows. Fields are read from the first row, which is the only
+/// one unless the query groups or aggregates.
+fn eval(expr: &Expr, group: &[&JSONValue]) -> Result<JSONValue, SqlError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Column(path) => {
+            let mut value = match group.first() {
+                Some(row) => *row,
+                None => return Ok(JSONValue::Null),
+            };
+            for name in path {
+                match value {
+                    JSONValue::Object(object) => match object.get(name) {
+                        Some(field) => value = field,
+                        None => return Ok(JSONValue::Null),
+                    },
+                    _ => return Ok(JSONValue::Null),
+                }
+            }
+            Ok(value.clone())
+        }
+        Expr::Aggregate(aggregate, arg) => {
+            let Some(arg) = arg else {
+                return Ok(integer(group.len() as i64));
+            };
+            let mut values = Vec::with_capacity(group.len());
+            for row in group {
+                let value = eval(arg, &[row])?;
+                if !value.is_null() {
+                    values.push(value);
+                }
+            }
+            aggregate_values(*aggregate, values)
+        }
+        Expr::Neg(expr) => match eval(expr, group)? {
+            JSONValue::Null => Ok(JSONValue::Null),
+            JSONValue::Number(num) => Ok(arithmetic(BinOp::Sub, SignedNum64::Integer(0), num)),
+            value => Err(type_error(format!("cannot negate {}", value.type_name()))),
+        },
+        Expr::Not(expr) => Ok(truth_value(truth(&eval(expr, group)?)?.map(|b| !b))),
+        Expr::And(left, right) => {
+            let l = truth(&eval(left, group)?)?;
+            if l == Some(false) {
+                return Ok(JSONValue::False);
+            }
+            let r = truth(&eval(right, group)?)?;
+            Ok(match (l, r) {
+                (_, Some(false)) => JSONValue::False,
+                (Some(true), Some(true)) => JSONValue::True,
+                _ => JSONValue::Null,
+            })
+        }
+        Expr::Or(left, right) => {
+            let l = truth(&eval(left, group)?)?;
+            if l == Some(true) {
+                return Ok(JSONValue::True);
+            }
+            let r = truth(&eval(right, group)?)?;
+            Ok(match (l, r) {
+                (_, Some(true)) => JSONValue::True,
+                (Some(false), Some(false)) => JSONValue::False,
+                _ => JSONValue::Null,
+            })
+        }
+        Expr::IsNull(expr, negated) => Ok(boolean(eval(expr, group)?.is_null() != *negated)),
+        Expr::Binary(op, left, right) => binary(*op, &eval(left, group)?, &eval(right, group)?),
+    }
+}
+
+fn integer(value: i64) -> JSONValue {
+    JSONValue::Number(SignedNum64::Integer(value))
+}
+
+fn aggregate_values(aggregate: Aggregate, values: Vec<JSONValue>) -> Result<JSONValue, SqlError> {
+    match aggregate {
+        Aggregate::Count => Ok(integer(values.len() as i64)),
+        Aggregate::Min => Ok(values
+            .into_iter()
+            .min_by(compare)
+            .unwrap_or(JSONValue::Null)),
+        Aggregate::Max => Ok(values
+            .into_iter()
+            .max_by(compare)
+            .unwrap_or(JSONValue::Null)),
+        Aggregate::Sum | Aggregate::Avg => {
+            let count = values.len();
+            let mut sum = None;
+            for value in values {
+                let JSONValue::Number(num) = value else {
+                    return Err(type_error(format!(
+                        "cannot {} {} values",
+                        if aggregate == Aggregate::Sum {
+                            "sum"
+                        } else {
+                            "average"
+                        },
+                        value.type_name()
+                    )));
+                };
+                sum = Some(match sum {
+                    Some(JSONValue::Number(total)) => arithmetic(BinOp::Add, total, num),
+                    _ => JSONValue::Number(num),
+                });
+            }
+            Ok(match (aggregate, sum) {
+                (Aggregate::Avg, Some(JSONValue::Number(total))) => {
+                    JSONValue::Number(SignedNum64::Float(total.as_f64() / count as f64))
+                }
+                (_, sum) => sum.unwrap_or(JSONValue::Null),
+            })
+        }
+    }
+}
+
+fn binary(op: BinOp, left: &JSONValue, right: &JSONValue) -> Result<JSONValue, SqlError> {
+    if left.is_null() || right.is_null() {
+        return Ok(JSONValue::Null);
+    }
+    let ordering = || compare(left, right);
+    let comparable = || {
+        matches!(
+            (left, right),
+            (JSONValue::Number(_), JSONValue::Number(_))
+                | (JSONValue::String(_), JSONValue::String(_))
+                | (
+                    JSONValue::True | JSONValue::False,
+                    JSONValue::True | JSONValue::False
+                )
+        )
+    };
+    let result = match op {
+        BinOp::Eq => boolean(ordering().is_eq()),
+        BinOp::Ne => boolean(ordering().is_ne()),
+        BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge if !comparable() => JSONValue::Null,
+        BinOp::Lt => boolean(ordering().is_lt()),
+        BinOp::Le => boolean(ordering().is_le()),
+        BinOp::Gt => boolean(ordering().is_gt()),
+        BinOp::Ge => boolean(ordering().is_ge()),
+        _ => match (left, right) {
+            (JSONValue::Number(l), JSONValue::Number(r)) => arithmetic(op, *l, *r),
+            _ => {
+                return Err(type_error(format!(
+                    "cannot apply {:?} to {} and {}",
+                    op,
+                    left.type_name(),
+                    right.type_name()
+                )))
+            }
+        },
+    };
+    Ok(result)
+}
+
+/// Applies an arithmetic operator; division or remainder by zero is null.
+fn arithmetic(op: BinOp, left: SignedNum64, right: SignedNum64) -> JSONValue {
+    let op = match op {
+        BinOp::Add => ArithmeticOp::Add,
+        BinOp::Sub => ArithmeticOp::Sub,
+        BinOp::Mul => ArithmeticOp::Mul,
+        BinOp::Div => ArithmeticOp::Div,
+        _ => ArithmeticOp::Mod,
+    };
+    left.arithmetic(op, right)
+        .map_or(JSONValue::Null, JSONValue::Number)
+}
@@ -0,0 +1,303 @@
+mod ast;
+mod eval;
+mod parser;
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::json_path::JSONPathErrorKind;
+use super::json_value::JSONValue;
+use ast::Select;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SqlErrorKind {
+    UnexpectedCharacter,
+    UnexpectedToken,
+    UnexpectedEnd,
+    /// A malformed number, or a `LIMIT` or `OFFSET` that is not a non-negative integer.
+    InvalidNumber,
+    /// The `FROM` clause is not a valid JSONPath.
+    InvalidPath(JSONPathErrorKind),
+    UnknownFunction(String),
+    /// An aggregate in `WHERE`, `GROUP BY` or the argument of another aggregate.
+    MisplacedAggregate,
+    /// A value of the wrong type was used at run time, e.g. `sum(name)`.
+    TypeError(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SqlError {
+    kind: SqlErrorKind,
+    position: Option<usize>,
+}
+
+impl SqlError {
+    pub fn new(kind: SqlErrorKind) -> Self {
+        SqlError {
+            kind,
+            position: None,
+        }
+    }
+
+    /// Creates an error located at `position`, a byte offset into the query text.
+    pub fn with_position(kind: SqlErrorKind, position: usize) -> Self {
+        SqlError {
+            kind,
+            position: Some(position),
+        }
+    }
+
+    pub fn kind(&self) -> &SqlErrorKind {
+        &self.kind
+    }
+
+    /// The position of a syntax error; run-time errors have none.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+}
+
+impl fmt::Display for SqlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{:?} at position {}", self.kind, position),
+            None => write!(f, "{:?}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for SqlError {}
+
+/// A compiled SQL-like query that treats an array of objects as a table:
+///
+/// ```text
+/// SELECT columns | * FROM <JSONPath> [WHERE ...] [GROUP BY ...] [HAVING ...]
+///     [ORDER BY expr [ASC | DESC], ...] [LIMIT n [OFFSET m]]
+/// ```
+///
+/// If the path selects a single array, its elements are the rows; otherwise the selected nodes
+/// are. Columns are fields of the row, dotted for nested objects (`address.city`) and
+/// double-quoted when they are not plain identifiers or clash with a keyword. Missing fields are
+/// null, and comparisons with null are unknown as in SQL. The aggregates are `count`, `sum`,
+/// `avg`, `min` and `max`; other expressions in a grouped query read the first row of the group.
+///
+/// The result is an array with one object per output row, keyed by the column aliases or the
+/// text of the column expressions.
+///
+/// ```
+/// use rust_json::json;
+/// use rust_json::json::sql::SqlQuery;
+///
+/// let value = json!({"users": [
+///     {"name": "Alice", "age": 15},
+///     {"name": "Bob", "age": 42},
+///     {"name": "Carol", "age": 30}
+/// ]});
+/// let query = SqlQuery::parse("SELECT name, age FROM $.users WHERE age > 18 ORDER BY age").unwrap();
+/// assert_eq!(
+///     json!([{"name": "Carol", "age": 30}, {"name": "Bob", "age": 42}]),
+///     query.run(&value).unwrap()
+/// );
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct SqlQuery(Select);
+
+impl SqlQuery {
+    pub fn parse(text: &str) -> Result<Self, SqlError> {
+        parser::Parser::parse(text).map(Self)
+    }
+
+    pub fn run(&self, value: &JSONValue) -> Result<JSONValue, SqlError> {
+        eval::run(&self.0, value)
+    }
+}
+
+impl FromStr for SqlQuery {
+    type Err = SqlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl JSONValue {
+    /// Parses `query` as an `SqlQuery` and runs it against this value.
+    pub fn sql(&self, query: &str) -> Result<JSONValue, SqlError> {
+        SqlQuery::parse(query)?.run(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json;
+
+    fn users() -> JSONValue {
+        json!({"users": [
+            {"name": "Alice", "age": 15, "team": "red", "address": {"city": "Oslo"}},
+            {"name": "Bob", "age": 42, "team": "blue", "address": {"city": "Rome"}},
+            {"name": "Carol", "age": 30, "team": "red"},
+            {"name": "Dave", "age": 27, "team": "blue", "address": {"city": "Oslo"}},
+            {"name": "Eve", "team": "green"}
+        ]})
+    }
+
+    #[test]
+    fn select() {
+        let value = users();
+        assert_eq!(
+            json!([{"name": "Bob", "age": 42}, {"name": "Carol", "age": 30}]),
+            value
+                .sql("select name, age from $.users where age > 28 order by age desc")
+                .unwrap()
+        );
+        assert_eq!(
+            json!([{"who": "Alice", "next": 16, "address.city": "Oslo"}]),
+            value
+                .sql("SELECT name AS who, age + 1 AS next, address.city FROM $.users LIMIT 1")
+                .unwrap()
+        );
+        assert_eq!(
+            // Nulls sort first, so the offset skips Eve.
+            json!([{"name": "Alice"}, {"name": "Dave"}]),
+            value
+                .sql("SELECT name FROM $.users ORDER BY age LIMIT 2 OFFSET 1")
+                .unwrap()
+        );
+        assert_eq!(
+            json!([{"name": "Eve", "team": "green"}]),
+            value
+                .sql("SELECT * FROM $.users WHERE age IS NULL")
+                .unwrap()
+        );
+        assert_eq!(
+            json!([{"age * 2": 30}]),
+            value.sql("SELECT age * 2 FROM $.users[0]").unwrap()
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let value = json!([{"a": i64::MIN, "b": 7.5}]);
+        assert_eq!(
+            json!([{"q": -(i64::MIN as f64), "r": 0, "z": null, "m": 1, "h": 3.75}]),
+            value
+                .sql("SELECT a / -1 AS q, a % -1 AS r, a / 0 AS z, b % 2 AS m, b / 2 AS h FROM $")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        let value = users();
+        let names = |query: &str| {
+            let rows = value.sql(query).unwrap();
+            rows.as_array()
+                .unwrap()
+                .iter()
+                .map(|row| row.as_object().unwrap().get("name").unwrap().clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![json!("Alice"), json!("Dave")],
+            names("SELECT name FROM $.users WHERE address.city = 'Oslo'")
+        );
+        assert_eq!(
+            vec![json!("Bob"), json!("Carol"), json!("Eve")],
+            names("SELECT name FROM $.users WHERE NOT address.city = 'Oslo' OR address IS NULL")
+        );
+        assert_eq!(
+            vec![json!("Carol")],
+            names("SELECT name FROM $.users WHERE team <> 'blue' AND (age >= 18)")
+        );
+        assert_eq!(
+            vec![json!("Alice"), json!("Carol")],
+            names("SELECT \"name\" FROM $.users[?@.team == 'red']")
+        );
+        assert!(names("SELECT name FROM $.users WHERE age > 'x'").is_empty());
+    }
+
+    #[test]
+    fn aggregates() {
+        let value = users();
+        assert_eq!(
+            json!([
+                {"team": "blue", "count(*)": 2, "total": 69, "oldest": 42},
+                {"team": "red", "count(*)": 2, "total": 45, "oldest": 30},
+                {"team": "green", "count(*)": 1, "total": null, "oldest": null}
+            ]),
+            value
+                .sql(
+                    "SELECT team, count(*), SUM(age) AS total, max(age) AS oldest \
+                     FROM $.users GROUP BY team ORDER BY total DESC"
+                )
+                .unwrap()
+        );
+        assert_eq!(
+            json!([{"n": 5, "aged": 4, "avg(age)": 28.5, "min(name)": "Alice"}]),
+            value
+                .sql("SELECT count(*) AS n, count(age) AS aged, avg(age), min(name) FROM $.users")
+                .unwrap()
+        );
+        assert_eq!(
+            json!([{"team": "blue"}]),
+            value
+                .sql("SELECT team FROM $.users GROUP BY team HAVING min(age) > 20")
+                .unwrap()
+        );
+        assert_eq!(
+            json!([{"count(*)": 0, "sum(age)": null}]),
+            value
+                .sql("SELECT count(*), sum(age) FROM $.users WHERE age > 100")
+                .unwrap()
+        );
+        assert_eq!(
+            json!([{"n": 1, "count(*)": 2}, {"n": 2, "count(*)": 1}]),
+            json!([{"n": 1}, {"n": 2}, {"n": 1.0}])
+                .sql("SELECT n, count(*) FROM $ GROUP BY n")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |query: &str| SqlQuery::parse(query).unwrap_err();
+        assert_eq!(
+            SqlErrorKind::UnexpectedToken,
+            *error("SELECT FROM $.users").kind()
+        );
+        assert_eq!(SqlErrorKind::UnexpectedEnd, *error("SELECT a FROM").kind());
+        assert_eq!(
+            SqlErrorKind::UnexpectedCharacter,
+            *error("SELECT a FROM $ WHERE a ^ 1").kind()
+        );
+        assert_eq!(
+            SqlErrorKind::InvalidNumber,
+            *error("SELECT a FROM $ LIMIT 1.5").kind()
+        );
+        assert_eq!(
+            SqlErrorKind::UnknownFunction("median".into()),
+            *error("SELECT median(a) FROM $").kind()
+        );
+        assert_eq!(
+            SqlErrorKind::MisplacedAggregate,
+            *error("SELECT a FROM $ WHERE count(*) > 1").kind()
+        );
+
+        let path = error("SELECT a FROM $.users[");
+        assert_eq!(
+            SqlErrorKind::InvalidPath(JSONPathErrorKind::UnexpectedEnd),
+            *path.kind()
+        );
+        assert_eq!(Some(22), path.position());
+
+        let error = users().sql("SELECT sum(name) FROM $.users").unwrap_err();
+        assert!(matches!(error.kind(), SqlErrorKind::TypeError(_)));
+        assert_eq!(
+            "UnexpectedToken at position 9",
+            SqlQuery::parse("SELECT a b FROM $")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,533 @@
+use super::super::json_path::JSONPath;
+use super::super::json_value::JSONValue;
+use super::super::util::signed_num_64::SignedNum64;
+use super::ast::{Aggregate, BinOp, Column, Expr, OrderBy, Select};
+use super::{SqlError, SqlErrorKind};
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    /// A keyword or a bare identifier.
+    Ident(String),
+    /// A double-quoted identifier, never a keyword.
+    QuotedIdent(String),
+    String(String),
+    Number(SignedNum64),
+    Path(JSONPath),
+    Star,
+    Comma,
+    Dot,
+    LParen,
+    RParen,
+    Op(BinOp),
+}
+
+const KEYWORDS: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "GROUP", "BY", "HAVING", "ORDER", "ASC", "DESC", "LIMIT", "OFFSET",
+    "AS", "AND", "OR", "NOT", "IS", "NULL", "TRUE", "FALSE",
+];
+
+fn is_keyword(name: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(name))
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Splits `text` into tokens with their start and end byte offsets.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize, usize)>, SqlError> {
+    let error = |kind, position| SqlError::with_position(kind, position);
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while let Some(c) = text[pos..].chars().next() {
+        let start = pos;
+        let rest = &text[pos..];
+        let two = rest.get(..2).unwrap_or("");
+
+        let token = if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        } else if c == '$' {
+            let len = path_len(rest);
+            pos += len;
+            let path = JSONPath::parse(&rest[..len]).map_err(|e| {
+                error(
+                    SqlErrorKind::InvalidPath(e.kind().clone()),
+                    start + e.position(),
+                )
+            })?;
+            Token::Path(path)
+        } else if is_ident_start(c) {
+            let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+            pos += len;
+            Token::Ident(rest[..len].to_string())
+        } else if c.is_ascii_digit() {
+            let len = number_len(rest);
+            pos += len;
+            let text = &rest[..len];
+            let num = match text.parse::<i64>() {
+                Ok(num) => SignedNum64::Integer(num),
+                Err(_) => match text.parse::<f64>() {
+                    Ok(num) if num.is_finite() => SignedNum64::Float(num),
+                    _ => return Err(error(SqlErrorKind::InvalidNumber, start)),
+                },
+            };
+            Token::Number(num)
+        } else if c == '\'' || c == '"' {
+            let (value, len) =
+                quoted(rest, c).ok_or(error(SqlErrorKind::UnexpectedEnd, text.len()))?;
+            pos += len;
+            if c == '\'' {
+                Token::String(value)
+            } else {
+                Token::QuotedIdent(value)
+            }
+        } else if let Some(op) = match two {
+            "==" => Some(BinOp::Eq),
+            "!=" | "<>" => Some(BinOp::Ne),
+            "<=" => Some(BinOp::Le),
+            ">=" => Some(BinOp::Ge),
+            _ => None,
+        } {
+            pos += 2;
+            Token::Op(op)
+        } else {
+            pos += c.len_utf8();
+            match c {
+                '*' => Token::Star,
+                ',' => Token::Comma,
+                '.' => Token::Dot,
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                '=' => Token::Op(BinOp::Eq),
+                '<' => Token::Op(BinOp::Lt),
+                '>' => Token::Op(BinOp::Gt),
+                '+' => Token::Op(BinOp::Add),
+                '-' => Token::Op(BinOp::Sub),
+                '/' => Token::Op(BinOp::Div),
+                '%' => Token::Op(BinOp::Mod),
+                _ => return Err(error(SqlErrorKind::UnexpectedCharacter, start)),
+            }
+        };
+        tokens.push((token, start, pos));
+    }
+
+    Ok(tokens)
+}
+
+/// Returns the length of the JSONPath at the start of `text`, which ends at the first whitespace
+/// outside of brackets, parentheses and string literals.
+fn path_len(text: &str) -> usize {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '[' | '(' => depth += 1,
+                ']' | ')' if depth > 0 => depth -= 1,
+                c if c.is_whitespace() && depth == 0 => return i,
+                _ => {}
+            },
+        }
+    }
+    text.len()
+}
+
+fn number_len(text: &str) -> usize {
+    let digits = |text: &str| {
+        text.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len())
+    };
+    let mut len = digits(text);
+    if text[len..].starts_with('.') && text[len + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+        len += 1 + digits(&text[len + 1..]);
+    }
+    if text[len..].starts_with(['e', 'E']) {
+        let sign = usize::from(text[len + 1..].starts_with(['+', '-']));
+        let exponent = digits(&text[len + 1 + sign..]);
+        if exponent > 0 {
+            len += 1 + sign + exponent;
+        }
+    }
+    len
+}
+
+/// Reads a literal quoted with `quote`, where a doubled quote stands for itself. Returns the
+/// contents and the length including the quotes, or `None` if it is unterminated.
+fn quoted(text: &str, quote: char) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            match chars.peek() {
+                Some(&(_, next)) if next == quote => {
+                    chars.next();
+                }
+                _ => return Some((value, i + 1)),
+            }
+        }
+        value.push(c);
+    }
+    None
+}
+
+pub(super) struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Token, usize, usize)>,
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn parse(text: &'a str) -> Result<Select, SqlError> {
+        let mut parser = Parser {
+            text,
+            tokens: tokenize(text)?,
+            index: 0,
+        };
+        let select = parser.select()?;
+        match parser.peek() {
+            None => Ok(select),
+            Some(_) => Err(parser.unexpected()),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, ..)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.index += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), SqlError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(name)) if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), SqlError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map_or(self.text.len(), |(_, start, _)| *start)
+    }
+
+    fn unexpected(&self) -> SqlError {
+        let kind = match self.peek() {
+            Some(_) => SqlErrorKind::UnexpectedToken,
+            None => SqlErrorKind::UnexpectedEnd,
+        };
+        SqlError::with_position(kind, self.position())
+    }
+
+    fn select(&mut self) -> Result<Select, SqlError> {
+        self.expect_keyword("SELECT")?;
+        let columns = if self.eat(&Token::Star) {
+            None
+        } else {
+            let mut columns = vec![self.column()?];
+            while self.eat(&Token::Comma) {
+                columns.push(self.column()?);
+            }
+            Some(columns)
+        };
+
+        self.expect_keyword("FROM")?;
+        let from = match self.next() {
+            Some(Token::Path(path)) => path,
+            _ => {
+                self.index -= 1;
+                return Err(self.unexpected());
+            }
+        };
+
+        let filter = if self.eat_keyword("WHERE") {
+            Some(self.row_expr()?)
+        } else {
+            None
+        };
+        let mut group_by = Vec::new();
+        if self.eat_keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            group_by.push(self.row_expr()?);
+            while self.eat(&Token::Comma) {
+                group_by.push(self.row_expr()?);
+            }
+        }
+        let having = if self.eat_keyword("HAVING") {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        let mut order_by = Vec::new();
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expr = self.expr()?;
+                let descending = self.eat_keyword("DESC");
+                if !descending {
+                    self.eat_keyword("ASC");
+                }
+                order_by.push(OrderBy { expr, descending });
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+        let mut limit = None;
+        let mut offset = 0;
+        if self.eat_keyword("LIMIT") {
+            limit = Some(self.count()?);
+            if self.eat_keyword("OFFSET") {
+                offset = self.count()?;
+            }
+        }
+
+        Ok(Select {
+            columns,
+            from,
+            filter,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    fn column(&mut self) -> Result<Column, SqlError> {
+        let start = self.index;
+        let expr = self.expr()?;
+        let alias = if self.eat_keyword("AS") {
+            match self.next() {
+                Some(Token::Ident(name)) if !is_keyword(&name) => Some(name),
+                Some(Token::QuotedIdent(name)) => Some(name),
+                _ => {
+                    self.index -= 1;
+                    return Err(self.unexpected());
+                }
+            }
+        } else {
+            None
+        };
+        let name = match (alias, &expr) {
+            (Some(alias), _) => alias,
+            (None, Expr::Column(path)) => path.join("."),
+            (None, _) => {
+                let (_, from, _) = self.tokens[start];
+                let (_, _, to) = self.tokens[self.index - 1];
+                self.text[from..to].to_string()
+            }
+        };
+        Ok(Column { expr, name })
+    }
+
+    /// Parses an expression evaluated per row, where aggregates are not allowed.
+    fn row_expr(&mut self) -> Result<Expr, SqlError> {
+        let position = self.position();
+        let expr = self.expr()?;
+        if expr.has_aggregate() {
+            return Err(SqlError::with_position(
+                SqlErrorKind::MisplacedAggregate,
+                position,
+            ));
+        }
+        Ok(expr)
+    }
+
+    /// Parses a non-negative integer for `LIMIT` and `OFFSET`.
+    fn count(&mut self) -> Result<usize, SqlError> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Number(SignedNum64::Integer(num))) => usize::try_from(num)
+                .map_err(|_| SqlError::with_position(SqlErrorKind::InvalidNumber, position)),
+            Some(Token::Number(_)) => Err(SqlError::with_position(
+                SqlErrorKind::InvalidNumber,
+                position,
+            )),
+            _ => {
+                self.index -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, SqlError> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("OR") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, SqlError> {
+        let mut expr = self.not()?;
+        while self.eat_keyword("AND") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, SqlError> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, SqlError> {
+        let left = self.additive()?;
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull(Box::new(left), negated));
+        }
+        match self.peek() {
+            Some(Token::Op(
+                op @ (BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge),
+            )) => {
+                let op = *op;
+                self.index += 1;
+                Ok(Expr::Binary(op, Box::new(left), Box::new(self.additive()?)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, SqlError> {
+        let mut expr = self.multiplicative()?;
+        while let Some(Token::Op(op @ (BinOp::Add | BinOp::Sub))) = self.peek() {
+            let op = *op;
+            self.index += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.multiplicative()?));
+        }
+        Ok(expr)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, SqlError> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => BinOp::Mul,
+                Some(Token::Op(op @ (BinOp::Div | BinOp::Mod))) => *op,
+                _ => break,
+            };
+            self.index += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, SqlError> {
+        if self.eat(&Token::Op(BinOp::Sub)) {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, SqlError> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Number(num)) => Ok(Expr::Literal(JSONValue::Number(num))),
+            Some(Token::String(s)) => Ok(Expr::Literal(JSONValue::String(s))),
+            Some(Token::LParen) => {
+                let expr = self.expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("NULL") => {
+                Ok(Expr::Literal(JSONValue::Null))
+            }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("TRUE") => {
+                Ok(Expr::Literal(JSONValue::True))
+            }
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case("FALSE") => {
+                Ok(Expr::Literal(JSONValue::False))
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.index += 1;
+                self.aggregate(name, position)
+            }
+            Some(Token::Ident(name)) if !is_keyword(&name) => self.column_ref(name),
+            Some(Token::QuotedIdent(name)) => self.column_ref(name),
+            _ => {
+                self.index -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+
+    /// Parses the arguments of an aggregate call after the opening parenthesis.
+    fn aggregate(&mut self, name: String, position: usize) -> Result<Expr, SqlError> {
+        let aggregate = Aggregate::from_name(&name).ok_or_else(|| {
+            SqlError::with_position(SqlErrorKind::UnknownFunction(name), position)
+        })?;
+        let arg = if aggregate == Aggregate::Count && self.eat(&Token::Star) {
+            None
+        } else {
+            Some(Box::new(self.row_expr()?))
+        };
+        self.expect(&Token::RParen)?;
+        Ok(Expr::Aggregate(aggregate, arg))
+    }
+
+    /// Parses the rest of a dotted column reference after its first name.
+    fn column_ref(&mut self, first: String) -> Result<Expr, SqlError> {
+        let mut path = vec![first];
+        while self.eat(&Token::Dot) {
+            match self.next() {
+                Some(Token::Ident(name) | Token::QuotedIdent(name)) => path.push(name),
+                _ => {
+                    self.index -= 1;
+                    return Err(self.unexpected());
+                }
+            }
+        }
+        Ok(Expr::Column(path))
+    }
+}
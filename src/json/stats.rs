@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem::{align_of, size_of, size_of_val};
+
+use super::json_value::{JSONValue, Key};
+use super::object::JSONObject;
+use super::path::Path;
+use super::visit::Visitor;
+
+/// How many entries `Stats::largest_arrays` keeps.
+const LARGEST_ARRAYS: usize = 10;
+
+/// The number of values of each JSON type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct TypeCounts {
+    pub null: usize,
+    pub boolean: usize,
+    pub number: usize,
+    pub string: usize,
+    pub array: usize,
+    pub object: usize,
+}
+
+impl TypeCounts {
+    pub fn total(&self) -> usize {
+        self.null + self.boolean + self.number + self.string + self.array + self.object
+    }
+}
+
+/// A summary of the shape and size of a document, computed by `JSONValue::stats`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Stats {
+    /// The length of the longest path, so 0 for a scalar and 1 for a flat array or object.
+    pub max_depth: usize,
+    pub counts: TypeCounts,
+    /// How many objects contain each key.
+    pub key_frequency: HashMap<Key, usize>,
+    /// The number of strings by byte length, keyed by the largest power of two not above the
+    /// length, or 0 for empty strings: key 8 counts strings of 8 to 15 bytes.
+    pub string_lengths: BTreeMap<usize, usize>,
+    /// The paths and lengths of the largest arrays, longest first, at most ten.
+    pub largest_arrays: Vec<(Path, usize)>,
+    /// The estimated heap allocation of the tree, including spare capacity and the key index of
+    /// every object. Keys shared through `ParserOptions::intern_keys` are counted once. The root
+    /// value itself is not included, as it is not necessarily on the heap.
+    pub heap_bytes: usize,
+}
+
+impl Stats {
+    /// Returns the `n` most frequent keys with their counts, most frequent first.
+    pub fn most_common_keys(&self, n: usize) -> Vec<(&Key, usize)> {
+        let mut keys: Vec<_> = self.key_frequency.iter().map(|(k, c)| (k, *c)).collect();
+        keys.sort_by(|(k1, c1), (k2, c2)| c2.cmp(c1).then_with(|| k1.cmp(k2)));
+        keys.truncate(n);
+        keys
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    stats: Stats,
+    arrays: Vec<(Path, usize)>,
+    seen_keys: HashSet<*const u8>,
+}
+
+impl Visitor for Collector {
+    fn enter_object(&mut self, path: &Path, object: &JSONObject) {
+        self.stats.max_depth = self.stats.max_depth.max(path.len());
+        self.stats.counts.object += 1;
+        self.stats.heap_bytes += object.table_heap_bytes();
+    }
+
+    fn enter_array(&mut self, path: &Path, array: &Vec<JSONValue>) {
+        self.stats.max_depth = self.stats.max_depth.max(path.len());
+        self.stats.counts.array += 1;
+        self.stats.heap_bytes += array.capacity() * size_of::<JSONValue>();
+        self.arrays.push((path.clone(), array.len()));
+    }
+
+    fn visit_key(&mut self, _path: &Path, key: &Key) {
+        *self.stats.key_frequency.entry(key.clone()).or_default() += 1;
+        if self.seen_keys.insert(key.as_ptr()) {
+            // Reference counts followed by the text, padded to the counts' alignment.
+            self.stats.heap_bytes +=
+                (2 * size_of::<usize>() + key.len()).next_multiple_of(align_of::<usize>());
+        }
+    }
+
+    fn visit_scalar(&mut self, path: &Path, value: &JSONValue) {
+        self.stats.max_depth = self.stats.max_depth.max(path.len());
+        let counts = &mut self.stats.counts;
+        match value {
+            JSONValue::Null => counts.null += 1,
+            JSONValue::True | JSONValue::False => counts.boolean += 1,
+            JSONValue::Number(_) => counts.number += 1,
+            JSONValue::String(s) => {
+                counts.string += 1;
+                self.stats.heap_bytes += s.capacity();
+                self.string_length(s.len());
+            }
+            JSONValue::Utf16String(s) => {
+                counts.string += 1;
+                self.stats.heap_bytes += size_of_val(s.units());
+                self.string_length(s.to_string_lossy().len());
+            }
+            JSONValue::Array(_) | JSONValue::Object(_) => unreachable!(),
+        }
+    }
+}
+
+impl Collector {
+    fn string_length(&mut self, len: usize) {
+        let bucket = match len {
+            0 => 0,
+            len => 1 << len.ilog2(),
+        };
+        *self.stats.string_lengths.entry(bucket).or_default() += 1;
+    }
+}
+
+impl JSONValue {
+    /// Summarizes the shape and memory use of the tree; see `Stats`.
+    pub fn stats(&self) -> Stats {
+        let mut collector = Collector::default();
+        self.visit(&mut collector);
+
+        // Arrays are visited in document order, and the stable sort keeps that order for ties.
+        collector.arrays.sort_by(|(_, a), (_, b)| b.cmp(a));
+        collector.arrays.truncate(LARGEST_ARRAYS);
+        let mut stats = collector.stats;
+        stats.largest_arrays = collector.arrays;
+        stats
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parser::Parser;
+    use super::super::parser_options::ParserOptions;
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn shape() {
+        let value = json!({
+            "users": [{"name": "", "tags": ["a", "b"]}, {"name": "Alice", "tags": []}],
+            "total": 2,
+            "ok": true,
+            "next": null
+        });
+        let stats = value.stats();
+
+        assert_eq!(4, stats.max_depth);
+        assert_eq!(
+            TypeCounts {
+                null: 1,
+                boolean: 1,
+                number: 1,
+                string: 4,
+                array: 3,
+                object: 3,
+            },
+            stats.counts
+        );
+        assert_eq!(13, stats.counts.total());
+        assert_eq!(
+            vec![(&Key::from("name"), 2), (&Key::from("tags"), 2)],
+            stats.most_common_keys(2)
+        );
+        assert_eq!(
+            BTreeMap::from([(0, 1), (1, 2), (4, 1)]),
+            stats.string_lengths
+        );
+        assert_eq!(
+            vec!["$.users", "$.users[0].tags", "$.users[1].tags"],
+            stats
+                .largest_arrays
+                .iter()
+                .map(|(path, _)| path.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, json!(1).stats().max_depth);
+    }
+
+    #[test]
+    fn heap_bytes() {
+        assert_eq!(0, json!(null).stats().heap_bytes);
+        assert_eq!(
+            4 * size_of::<JSONValue>(),
+            Parser::parse("[1, 2, 3, 4]").unwrap().stats().heap_bytes
+        );
+
+        let text = format!("[{}]", vec!["{\"identifier\": 1}"; 100].join(","));
+        let plain = Parser::parse(&text).unwrap().stats().heap_bytes;
+        let options = ParserOptions {
+            intern_keys: true,
+            ..ParserOptions::default()
+        };
+        let interned = Parser::parse_with_options(&text, &options)
+            .unwrap()
+            .stats()
+            .heap_bytes;
+        assert_eq!(99 * 32, plain - interned);
+    }
+}
@@ -1,59 +1,159 @@
+use std::fmt::Write;
+
+use super::parser_error::ParserErrorKind;
+use super::parser_options::{LoneSurrogates, StringValidation};
+use super::utf16_string::Utf16String;
+
+fn escape_char(result: &mut String, c: char) {
+    match c {
+        '"' => result.push_str("\\\""),
+        '\\' => result.push_str("\\\\"),
+        '\x08' => result.push_str("\\b"),
+        '\x0c' => result.push_str("\\f"),
+        '\n' => result.push_str("\\n"),
+        '\r' => result.push_str("\\r"),
+        '\t' => result.push_str("\\t"),
+        c if c < '\x20' => write!(result, "\\u{:04x}", c as u32).unwrap(),
+        c => result.push(c),
+    }
+}
+
 pub fn escape(s: &str) -> String {
     let mut result = String::new();
     for c in s.chars() {
+        escape_char(&mut result, c);
+    }
+    result
+}
+
+/// Escapes a UTF-16 string, writing unpaired surrogates as `\uXXXX` escapes.
+pub fn escape_utf16(s: &Utf16String) -> String {
+    let mut result = String::new();
+    for c in char::decode_utf16(s.units().iter().copied()) {
         match c {
-            '"' => result.push_str("\\\""),
-            '\\' => result.push_str("\\\\"),
-            '\x08' => result.push_str("\\b"),
-            '\x0c' => result.push_str("\\f"),
-            '\n' => result.push_str("\\n"),
-            '\r' => result.push_str("\\r"),
-            '\t' => result.push_str("\\t"),
-            c => result.push(c),
+            Ok(c) => escape_char(&mut result, c),
+            Err(e) => write!(result, "\\u{:04x}", e.unpaired_surrogate()).unwrap(),
         }
     }
     result
 }
 
-pub fn unescape(s: &str) -> String {
-    let mut result = String::new();
-    let mut chars = s.chars();
-    while let Some(c) = chars.next() {
+/// The contents of a string literal after escape sequences are resolved.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Unescaped {
+    String(String),
+    Utf16String(Utf16String),
+}
+
+impl Unescaped {
+    fn push(&mut self, c: char) {
+        match self {
+            Unescaped::String(s) => s.push(c),
+            Unescaped::Utf16String(s) => s.push(c),
+        }
+    }
+
+    /// Appends an unpaired surrogate, switching to a `Utf16String` if needed.
+    fn push_lone_surrogate(&mut self, unit: u16) {
+        if let Unescaped::String(s) = self {
+            *self = Unescaped::Utf16String(Utf16String::from(s.as_str()));
+        }
+        if let Unescaped::Utf16String(s) = self {
+            s.push_unit(unit);
+        }
+    }
+}
+
+enum Escaped {
+    Char(char),
+    LoneSurrogate(u16),
+}
+
+fn is_high_surrogate(n: u32) -> bool {
+    (0xd800..0xdc00).contains(&n)
+}
+
+fn is_low_surrogate(n: u32) -> bool {
+    (0xdc00..0xe000).contains(&n)
+}
+
+/// Consumes the four characters following `\u`. Returns `None` if there are fewer than four
+/// characters left or any of them is not a hex digit.
+fn read_hex4(chars: &mut std::iter::Peekable<std::str::CharIndices>) -> Option<u32> {
+    let hex: String = chars.take(4).map(|(_, c)| c).collect();
+    if hex.len() == 4 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        u32::from_str_radix(&hex, 16).ok()
+    } else {
+        None
+    }
+}
+
+/// Reads a `\uXXXX` escape whose leading `\u` has already been consumed, combining it with a
+/// following low surrogate escape if it is a high surrogate.
+fn read_unicode_escape(chars: &mut std::iter::Peekable<std::str::CharIndices>) -> Option<Escaped> {
+    let n = read_hex4(chars)?;
+
+    if is_high_surrogate(n) {
+        let mut lookahead = chars.clone();
+        if let (Some((_, '\\')), Some((_, 'u'))) = (lookahead.next(), lookahead.next()) {
+            if let Some(low) = read_hex4(&mut lookahead).filter(|low| is_low_surrogate(*low)) {
+                *chars = lookahead;
+                let c = char::from_u32(0x10000 + ((n - 0xd800) << 10) + (low - 0xdc00))?;
+                return Some(Escaped::Char(c));
+            }
+        }
+    }
+
+    Some(match char::from_u32(n) {
+        Some(c) => Escaped::Char(c),
+        None => Escaped::LoneSurrogate(n as u16),
+    })
+}
+
+/// Resolves escape sequences in the contents of a string literal. Lone surrogates are replaced
+/// with U+FFFD unless `lone_surrogates` asks to preserve them, in which case the result is a
+/// `Utf16String`. On failure, returns the error kind with the byte offset of the offending
+/// character within `s`.
+pub fn unescape(
+    s: &str,
+    validation: StringValidation,
+    lone_surrogates: LoneSurrogates,
+) -> Result<Unescaped, (ParserErrorKind, usize)> {
+    let strict = validation == StringValidation::Strict;
+    let mut result = Unescaped::String(String::new());
+    let mut chars = s.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
         if c == '\\' {
             match chars.next() {
-                Some('"') => result.push('"'),
-                Some('/') => result.push('/'),
-                Some('\\') => result.push('\\'),
-                Some('b') => result.push('\x08'),
-                Some('f') => result.push('\x0c'),
-                Some('n') => result.push('\n'),
-                Some('r') => result.push('\r'),
-                Some('t') => result.push('\t'),
-                Some('u') => {
-                    let mut hex = String::new();
-                    for _ in 0..4 {
-                        match chars.next() {
-                            Some(c) => hex.push(c),
-                            None => {
-                                result.push('?');
-                                return result;
-                            }
-                        }
+                Some((_, '"')) => result.push('"'),
+                Some((_, '/')) => result.push('/'),
+                Some((_, '\\')) => result.push('\\'),
+                Some((_, 'b')) => result.push('\x08'),
+                Some((_, 'f')) => result.push('\x0c'),
+                Some((_, 'n')) => result.push('\n'),
+                Some((_, 'r')) => result.push('\r'),
+                Some((_, 't')) => result.push('\t'),
+                Some((_, 'u')) => match read_unicode_escape(&mut chars) {
+                    Some(Escaped::Char(c)) => result.push(c),
+                    Some(Escaped::LoneSurrogate(_))
+                        if lone_surrogates == LoneSurrogates::Replace =>
+                    {
+                        result.push(char::REPLACEMENT_CHARACTER)
                     }
-                    match u32::from_str_radix(&hex, 16) {
-                        Ok(n) => match char::from_u32(n) {
-                            Some(c) => result.push(c),
-                            None => result.push('?'),
-                        },
-                        Err(_) => result.push('?'),
-                    }
-                }
-                Some(c) => result.push(c),
+                    Some(Escaped::LoneSurrogate(unit)) => result.push_lone_surrogate(unit),
+                    None if strict => return Err((ParserErrorKind::InvalidEscape, index)),
+                    None => result.push('?'),
+                },
+                Some(_) if strict => return Err((ParserErrorKind::InvalidEscape, index)),
+                Some((_, c)) => result.push(c),
+                None if strict => return Err((ParserErrorKind::InvalidEscape, index)),
                 None => result.push('\\'),
             }
+        } else if c < '\x20' && strict {
+            return Err((ParserErrorKind::ControlCharacterInString, index));
         } else {
             result.push(c);
         }
     }
-    result
+    Ok(result)
 }
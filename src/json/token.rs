@@ -1,7 +1,10 @@
+use std::borrow::Cow;
+
+use super::utf16_string::Utf16String;
 use super::util::signed_num_64::SignedNum64;
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum Token {
+pub enum Token<'a> {
     BeginArray,
     EndArray,
     BeginObject,
@@ -12,5 +15,7 @@ pub enum Token {
     False,
     Null,
     Number(SignedNum64),
-    String(String),
+    /// Borrows from the input text unless the literal contains escape sequences.
+    String(Cow<'a, str>),
+    Utf16String(Utf16String),
 }
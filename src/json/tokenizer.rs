@@ -1,25 +1,52 @@
+use std::borrow::Cow;
+
 use regex::bytes::Regex;
 
+use super::parser_error::{ParserError, ParserErrorKind};
+use super::parser_options::{LoneSurrogates, ParserOptions, StringValidation};
+use super::string::Unescaped;
 use super::token::Token;
 use super::util::signed_num_64::SignedNum64;
 
 pub struct Tokenizer<'a> {
     index: usize,
     text: &'a [u8],
+    string_validation: StringValidation,
+    lone_surrogates: LoneSurrogates,
+    error: Option<ParserError>,
 }
 
 impl<'a> Tokenizer<'a> {
+    #[cfg(test)]
     pub fn new(text: &'a str) -> Self {
+        Self::with_options(text, &ParserOptions::default())
+    }
+
+    pub fn with_options(text: &'a str, options: &ParserOptions) -> Self {
         Self {
             index: 0,
             text: text.as_bytes(),
+            string_validation: options.string_validation,
+            lone_surrogates: options.lone_surrogates,
+            error: None,
         }
     }
 
-    pub fn tokenize(text: &'a str) -> Vec<Token> {
-        let tokenizer = Self::new(text);
-        let iter = TokenizerIterator { tokenizer };
-        iter.collect()
+    /// Splits `text` into tokens, each paired with the byte offset it starts at.
+    pub fn tokenize(
+        text: &'a str,
+        options: &ParserOptions,
+    ) -> Result<Vec<(Token<'a>, usize)>, ParserError> {
+        let mut tokenizer = Self::with_options(text, options);
+        let iter = TokenizerIterator {
+            tokenizer: &mut tokenizer,
+        };
+        let tokens = iter.collect();
+
+        match tokenizer.error {
+            Some(error) => Err(error),
+            None => Ok(tokens),
+        }
     }
 
     fn consume_whitespaces(&mut self) -> Option<()> {
@@ -35,7 +62,7 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
-    fn consume_char(&mut self) -> Option<Token> {
+    fn consume_char(&mut self) -> Option<Token<'a>> {
         let head_char = self.text.get(self.index)?;
         let token = match *head_char {
             b'[' => Some(Token::BeginArray),
@@ -54,7 +81,7 @@ impl<'a> Tokenizer<'a> {
         token
     }
 
-    fn consume_bool_and_null(&mut self) -> Option<Token> {
+    fn consume_bool_and_null(&mut self) -> Option<Token<'a>> {
         let sub4 = self.text.get((self.index)..(self.index + 4))?;
         let token = if sub4[0] == b't' && sub4[1] == b'r' && sub4[2] == b'u' && sub4[3] == b'e' {
             Some(Token::True)
@@ -70,7 +97,7 @@ impl<'a> Tokenizer<'a> {
         }
 
         let sub5 = self.text.get((self.index)..(self.index + 5))?;
-        return if sub5[0] == b'f'
+        if sub5[0] == b'f'
             && sub5[1] == b'a'
             && sub5[2] == b'l'
             && sub5[3] == b's'
@@ -80,10 +107,10 @@ impl<'a> Tokenizer<'a> {
             Some(Token::False)
         } else {
             None
-        };
+        }
     }
 
-    fn consume_string(&mut self) -> Option<Token> {
+    fn consume_string(&mut self) -> Option<Token<'a>> {
         let head_char = self.text.get(self.index)?;
         if *head_char != b'"' {
             return None;
@@ -93,7 +120,13 @@ impl<'a> Tokenizer<'a> {
         let start = self.index;
 
         loop {
-            let next_char = self.text.get(self.index)?;
+            let Some(next_char) = self.text.get(self.index) else {
+                self.error = Some(ParserError::with_position(
+                    ParserErrorKind::UnexpectedEOF,
+                    self.text.len(),
+                ));
+                return None;
+            };
             if *next_char == b'"' {
                 break;
             } else if *next_char == b'\\' {
@@ -115,13 +148,23 @@ impl<'a> Tokenizer<'a> {
         let end = self.index;
         self.index += 1;
 
-        let sub = self.text[start..end].to_owned();
-        let value = String::from_utf8(sub).unwrap();
-        let value = super::string::unescape(&value);
-        Some(Token::String(value))
+        // The quotes are ASCII, so the contents lie on UTF-8 boundaries.
+        let raw = std::str::from_utf8(&self.text[start..end]).unwrap();
+        if !raw.bytes().any(|b| b == b'\\' || b < 0x20) {
+            return Some(Token::String(Cow::Borrowed(raw)));
+        }
+
+        match super::string::unescape(raw, self.string_validation, self.lone_surrogates) {
+            Ok(Unescaped::String(value)) => Some(Token::String(Cow::Owned(value))),
+            Ok(Unescaped::Utf16String(value)) => Some(Token::Utf16String(value)),
+            Err((kind, offset)) => {
+                self.error = Some(ParserError::with_position(kind, start + offset));
+                None
+            }
+        }
     }
 
-    fn consume_int_number(&mut self) -> Option<Token> {
+    fn consume_int_number(&mut self) -> Option<Token<'a>> {
         let regex = Regex::new(r"-?(0|[1-9]\d*)").unwrap();
         let match_len = regex
             .captures_at(self.text, self.index)?
@@ -142,7 +185,7 @@ impl<'a> Tokenizer<'a> {
         Some(token)
     }
 
-    fn consume_float_number(&mut self) -> Option<Token> {
+    fn consume_float_number(&mut self) -> Option<Token<'a>> {
         let regex =
             Regex::new(r"-?(0|[1-9]\d*)((\.\d+)([eE][+\-]?\d+)?|(\.\d+)?([eE][+\-]?\d+))").unwrap();
         let match_len = regex
@@ -164,39 +207,57 @@ impl<'a> Tokenizer<'a> {
         Some(token)
     }
 
-    fn consume_number(&mut self) -> Option<Token> {
+    fn consume_number(&mut self) -> Option<Token<'a>> {
         self.consume_float_number()
             .or_else(|| self.consume_int_number())
     }
 
-    fn consume(&mut self) -> Option<Token> {
+    fn consume(&mut self) -> Option<Token<'a>> {
         self.consume_whitespaces();
 
         if self.index >= self.text.len() {
             return None;
         }
 
-        self.consume_char()
+        let token = self
+            .consume_char()
             .or_else(|| self.consume_bool_and_null())
-            .or_else(|| self.consume_string())
-            .or_else(|| self.consume_number())
+            .or_else(|| self.consume_string());
+        // A malformed string has already recorded its error.
+        if self.error.is_some() {
+            return None;
+        }
+        let token = token.or_else(|| self.consume_number());
+        if token.is_none() {
+            self.error = Some(ParserError::with_position(
+                ParserErrorKind::UnexpectedToken,
+                self.index,
+            ));
+        }
+        token
     }
 }
 
-struct TokenizerIterator<'a> {
-    tokenizer: Tokenizer<'a>,
+struct TokenizerIterator<'a, 'b> {
+    tokenizer: &'b mut Tokenizer<'a>,
 }
 
-impl<'a> Iterator for TokenizerIterator<'a> {
-    type Item = Token;
+impl<'a, 'b> Iterator for TokenizerIterator<'a, 'b> {
+    type Item = (Token<'a>, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.tokenizer.consume()
+        if self.tokenizer.error.is_some() {
+            return None;
+        }
+        self.tokenizer.consume_whitespaces();
+        let position = self.tokenizer.index;
+        self.tokenizer.consume().map(|token| (token, position))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::super::utf16_string::Utf16String;
     use super::*;
 
     #[test]
@@ -287,42 +348,103 @@ mod tests {
         fn string() {
             let mut tokenizer = Tokenizer::new("\"hello\"");
             assert_eq!(
-                Some(Token::String(String::from("hello"))),
+                Some(Token::String("hello".into())),
                 tokenizer.consume_string()
             );
 
             let mut tokenizer = Tokenizer::new("\"hello\"");
-            assert_eq!(
-                Some(Token::String(String::from("hello"))),
-                tokenizer.consume()
-            );
+            assert_eq!(Some(Token::String("hello".into())), tokenizer.consume());
         }
 
         #[test]
         fn string_with_escape() {
             let mut tokenizer = Tokenizer::new("\"hello\\\"\"");
             assert_eq!(
-                Some(Token::String(String::from("hello\""))),
+                Some(Token::String("hello\"".into())),
                 tokenizer.consume_string()
             );
 
             let mut tokenizer = Tokenizer::new("\"hello\\\"\"");
-            assert_eq!(
-                Some(Token::String(String::from("hello\""))),
-                tokenizer.consume()
-            );
+            assert_eq!(Some(Token::String("hello\"".into())), tokenizer.consume());
         }
 
         #[test]
         fn string_with_unicode() {
             let mut tokenizer = Tokenizer::new("\"\\u3042\"");
+            assert_eq!(Some(Token::String("あ".into())), tokenizer.consume_string());
+
+            let mut tokenizer = Tokenizer::new("\"\\u3042\"");
+            assert_eq!(Some(Token::String("あ".into())), tokenizer.consume());
+        }
+
+        #[test]
+        fn string_with_surrogate_pair() {
+            let mut tokenizer = Tokenizer::new("\"\\ud83d\\ude00\"");
+            assert_eq!(Some(Token::String("😀".into())), tokenizer.consume_string());
+        }
+
+        #[test]
+        fn string_with_lone_surrogate() {
+            let mut tokenizer = Tokenizer::new("\"\\ud83dx\"");
             assert_eq!(
-                Some(Token::String(String::from("あ"))),
+                Some(Token::String("\u{fffd}x".into())),
                 tokenizer.consume_string()
             );
+        }
 
-            let mut tokenizer = Tokenizer::new("\"\\u3042\"");
-            assert_eq!(Some(Token::String(String::from("あ"))), tokenizer.consume());
+        #[test]
+        fn string_with_preserved_lone_surrogate() {
+            let options = ParserOptions {
+                lone_surrogates: LoneSurrogates::Preserve,
+                ..Default::default()
+            };
+            let mut tokenizer = Tokenizer::with_options("\"a\\ud83d\"", &options);
+            assert_eq!(
+                Some(Token::Utf16String(Utf16String::from_units(vec![
+                    0x61, 0xd83d
+                ]))),
+                tokenizer.consume_string()
+            );
+
+            let mut tokenizer = Tokenizer::with_options("\"\\ud83d\\ude00\"", &options);
+            assert_eq!(Some(Token::String("😀".into())), tokenizer.consume_string());
+        }
+
+        #[test]
+        fn string_with_invalid_escape() {
+            let mut tokenizer = Tokenizer::new("  \"ab\\q\"");
+            assert_eq!(None, tokenizer.consume());
+            assert_eq!(
+                Some(ParserError::with_position(
+                    ParserErrorKind::InvalidEscape,
+                    5
+                )),
+                tokenizer.error
+            );
+        }
+
+        #[test]
+        fn first_string_error_wins() {
+            assert_eq!(
+                Err(ParserError::with_position(
+                    ParserErrorKind::InvalidEscape,
+                    2
+                )),
+                Tokenizer::tokenize("[\"\\x\"1, \"\\y\"]", &ParserOptions::default())
+            );
+        }
+
+        #[test]
+        fn string_with_control_character() {
+            let mut tokenizer = Tokenizer::new("\"a\tb\"");
+            assert_eq!(None, tokenizer.consume());
+            assert_eq!(
+                Some(ParserError::with_position(
+                    ParserErrorKind::ControlCharacterInString,
+                    2
+                )),
+                tokenizer.error
+            );
         }
     }
 
@@ -502,9 +624,6 @@ mod tests {
             Some(Token::Number(SignedNum64::Float(123.456))),
             tokenizer.consume()
         );
-        assert_eq!(
-            Some(Token::String(String::from("hello"))),
-            tokenizer.consume()
-        );
+        assert_eq!(Some(Token::String("hello".into())), tokenizer.consume());
     }
 }
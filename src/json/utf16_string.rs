@@ -0,0 +1,58 @@
+use std::fmt;
+
+/// A string stored as UTF-16 code units, which unlike `String` can hold unpaired surrogates.
+///
+/// The parser produces these only with `LoneSurrogates::Preserve`, and only for strings that
+/// cannot be represented as a `String`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Utf16String(Vec<u16>);
+
+impl Utf16String {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn from_units(units: Vec<u16>) -> Self {
+        Self(units)
+    }
+
+    pub fn units(&self) -> &[u16] {
+        &self.0
+    }
+
+    pub fn push(&mut self, c: char) {
+        let mut buf = [0; 2];
+        self.0.extend_from_slice(c.encode_utf16(&mut buf));
+    }
+
+    pub fn push_unit(&mut self, unit: u16) {
+        self.0.push(unit);
+    }
+
+    /// Returns `true` if the string contains an unpaired surrogate.
+    pub fn has_lone_surrogates(&self) -> bool {
+        char::decode_utf16(self.0.iter().copied()).any(|c| c.is_err())
+    }
+
+    /// Converts to a `String`, replacing unpaired surrogates with U+FFFD.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(&self.0)
+    }
+
+    /// Converts to a `String`, or returns `self` back if it contains unpaired surrogates.
+    pub fn into_string(self) -> Result<String, Self> {
+        String::from_utf16(&self.0).map_err(|_| self)
+    }
+}
+
+impl From<&str> for Utf16String {
+    fn from(s: &str) -> Self {
+        Self(s.encode_utf16().collect())
+    }
+}
+
+impl fmt::Display for Utf16String {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_string_lossy())
+    }
+}
@@ -1,5 +1,67 @@
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum SignedNum64 {
     Integer(i64),
     Float(f64),
 }
+
+impl SignedNum64 {
+    /// Returns the value as a float, rounding integers beyond 2^53.
+    pub fn as_f64(self) -> f64 {
+        match self {
+            SignedNum64::Integer(num) => num as f64,
+            SignedNum64::Float(num) => num,
+        }
+    }
+}
+
+/// The arithmetic operators shared by the jq and SQL evaluators.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+impl SignedNum64 {
+    /// Applies `op`, staying exact for integers where the result fits and falling back to floats
+    /// otherwise. `Mod` truncates both operands to integers, as jq and SQLite do. Returns `None`
+    /// when the divisor is zero.
+    pub(crate) fn arithmetic(self, op: ArithmeticOp, other: SignedNum64) -> Option<SignedNum64> {
+        use SignedNum64::{Float, Integer};
+
+        let truncate = |num: SignedNum64| match num {
+            Integer(num) => num,
+            Float(num) => num as i64,
+        };
+        if op == ArithmeticOp::Mod {
+            let (l, r) = (truncate(self), truncate(other));
+            // Only i64::MIN % -1 overflows, and its remainder is zero.
+            return (r != 0).then(|| Integer(l.checked_rem(r).unwrap_or(0)));
+        }
+
+        if let (Integer(l), Integer(r)) = (self, other) {
+            let exact = match op {
+                ArithmeticOp::Add => l.checked_add(r),
+                ArithmeticOp::Sub => l.checked_sub(r),
+                ArithmeticOp::Mul => l.checked_mul(r),
+                ArithmeticOp::Div if l.checked_rem(r) == Some(0) => l.checked_div(r),
+                _ => None,
+            };
+            if let Some(result) = exact {
+                return Some(Integer(result));
+            }
+        }
+
+        let (l, r) = (self.as_f64(), other.as_f64());
+        match op {
+            ArithmeticOp::Add => Some(Float(l + r)),
+            ArithmeticOp::Sub => Some(Float(l - r)),
+            ArithmeticOp::Mul => Some(Float(l * r)),
+            ArithmeticOp::Div if r == 0.0 => None,
+            ArithmeticOp::Div => Some(Float(l / r)),
+            ArithmeticOp::Mod => unreachable!(),
+        }
+    }
+}
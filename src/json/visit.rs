@@ -0,0 +1,219 @@
+use super::json_value::{JSONValue, Key};
+use super::object::JSONObject;
+use super::path::{Path, PathSegment};
+
+/// Read-only traversal of a `JSONValue` tree, driven by `JSONValue::visit`.
+///
+/// Every callback receives the path of the value it concerns. All methods do nothing by default,
+/// so implementors only override what they need.
+pub trait Visitor {
+    fn enter_object(&mut self, _path: &Path, _object: &JSONObject) {}
+
+    fn leave_object(&mut self, _path: &Path, _object: &JSONObject) {}
+
+    /// Receives the `Vec` rather than a slice so that the capacity is visible, e.g. to estimate
+    /// memory use.
+    fn enter_array(&mut self, _path: &Path, _array: &Vec<JSONValue>) {}
+
+    fn leave_array(&mut self, _path: &Path, _array: &Vec<JSONValue>) {}
+
+    /// Called for each object member before its value is visited. `path` ends with the key.
+    fn visit_key(&mut self, _path: &Path, _key: &Key) {}
+
+    /// Called for every value that is neither an object nor an array.
+    fn visit_scalar(&mut self, _path: &Path, _value: &JSONValue) {}
+}
+
+fn walk(value: &JSONValue, path: &mut Path, visitor: &mut (impl Visitor + ?Sized)) {
+    match value {
+        JSONValue::Object(obj) => {
+            visitor.enter_object(path, obj);
+            for (key, val) in obj {
+                path.push(PathSegment::Key(key.clone()));
+                visitor.visit_key(path, key);
+                walk(val, path, visitor);
+                path.pop();
+            }
+            visitor.leave_object(path, obj);
+        }
+        JSONValue::Array(arr) => {
+            visitor.enter_array(path, arr);
+            for (i, val) in arr.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                walk(val, path, visitor);
+                path.pop();
+            }
+            visitor.leave_array(path, arr);
+        }
+        _ => visitor.visit_scalar(path, value),
+    }
+}
+
+/// Rebuilding traversal of a `JSONValue` tree, driven by `JSONValue::fold`.
+///
+/// The default methods rebuild the tree unchanged. An override can return a replacement, and
+/// can call `fold_object_members` or `fold_array_elements` to keep folding the children.
+pub trait Fold {
+    fn fold_value(&mut self, path: &mut Path, value: JSONValue) -> JSONValue {
+        match value {
+            JSONValue::Object(obj) => self.fold_object(path, obj),
+            JSONValue::Array(arr) => self.fold_array(path, arr),
+            value => self.fold_scalar(path, value),
+        }
+    }
+
+    fn fold_object(&mut self, path: &mut Path, object: JSONObject) -> JSONValue {
+        JSONValue::Object(fold_object_members(self, path, object))
+    }
+
+    fn fold_array(&mut self, path: &mut Path, array: Vec<JSONValue>) -> JSONValue {
+        JSONValue::Array(fold_array_elements(self, path, array))
+    }
+
+    /// Returns the key to store an object member under. `path` ends with the original key.
+    fn fold_key(&mut self, _path: &Path, key: Key) -> Key {
+        key
+    }
+
+    /// Called for every value that is neither an object nor an array.
+    fn fold_scalar(&mut self, _path: &Path, value: JSONValue) -> JSONValue {
+        value
+    }
+}
+
+/// Folds each member of `object`, pushing its key onto `path` meanwhile.
+pub fn fold_object_members(
+    folder: &mut (impl Fold + ?Sized),
+    path: &mut Path,
+    object: JSONObject,
+) -> JSONObject {
+    let mut result = JSONObject::with_capacity(object.len());
+    for (key, val) in object {
+        path.push(PathSegment::Key(key.clone()));
+        let new_key = folder.fold_key(path, key);
+        let new_val = folder.fold_value(path, val);
+        path.pop();
+        result.insert(new_key, new_val);
+    }
+    result
+}
+
+/// Folds each element of `array`, pushing its index onto `path` meanwhile.
+pub fn fold_array_elements(
+    folder: &mut (impl Fold + ?Sized),
+    path: &mut Path,
+    array: Vec<JSONValue>,
+) -> Vec<JSONValue> {
+    let mut result = Vec::with_capacity(array.len());
+    for (i, val) in array.into_iter().enumerate() {
+        path.push(PathSegment::Index(i));
+        result.push(folder.fold_value(path, val));
+        path.pop();
+    }
+    result
+}
+
+impl JSONValue {
+    /// Walks the tree depth-first, calling `visitor` for every value in document order.
+    pub fn visit(&self, visitor: &mut (impl Visitor + ?Sized)) {
+        walk(self, &mut Path::root(), visitor);
+    }
+
+    /// Rebuilds the tree through `folder`.
+    pub fn fold(self, folder: &mut (impl Fold + ?Sized)) -> JSONValue {
+        folder.fold_value(&mut Path::root(), self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::parser::Parser;
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+    }
+
+    impl Visitor for Recorder {
+        fn enter_object(&mut self, path: &Path, _: &JSONObject) {
+            self.events.push(format!("enter object {}", path));
+        }
+
+        fn leave_object(&mut self, path: &Path, _: &JSONObject) {
+            self.events.push(format!("leave object {}", path));
+        }
+
+        fn enter_array(&mut self, path: &Path, _: &Vec<JSONValue>) {
+            self.events.push(format!("enter array {}", path));
+        }
+
+        fn leave_array(&mut self, path: &Path, _: &Vec<JSONValue>) {
+            self.events.push(format!("leave array {}", path));
+        }
+
+        fn visit_key(&mut self, path: &Path, key: &Key) {
+            self.events.push(format!("key {} {}", path, key));
+        }
+
+        fn visit_scalar(&mut self, path: &Path, value: &JSONValue) {
+            self.events
+                .push(format!("scalar {} {}", path, value.type_name()));
+        }
+    }
+
+    #[test]
+    fn visitor() {
+        let value = Parser::parse("{\"a\": [1, null], \"b\": true}").unwrap();
+        let mut recorder = Recorder::default();
+        value.visit(&mut recorder);
+
+        assert_eq!(
+            vec![
+                "enter object $",
+                "key $.a a",
+                "enter array $.a",
+                "scalar $.a[0] number",
+                "scalar $.a[1] null",
+                "leave array $.a",
+                "key $.b b",
+                "scalar $.b boolean",
+                "leave object $",
+            ],
+            recorder.events
+        );
+    }
+
+    struct Redact;
+
+    impl Fold for Redact {
+        fn fold_object(&mut self, path: &mut Path, object: JSONObject) -> JSONValue {
+            let mut object = fold_object_members(self, path, object);
+            if object.contains_key("PASSWORD") {
+                object.insert("PASSWORD", JSONValue::from("***"));
+            }
+            JSONValue::Object(object)
+        }
+
+        fn fold_key(&mut self, _: &Path, key: Key) -> Key {
+            key.to_uppercase().into()
+        }
+
+        fn fold_scalar(&mut self, path: &Path, value: JSONValue) -> JSONValue {
+            match path.segments().last() {
+                Some(PathSegment::Index(_)) => JSONValue::Null,
+                _ => value,
+            }
+        }
+    }
+
+    #[test]
+    fn fold() {
+        let value = Parser::parse("{\"user\": {\"password\": \"a\", \"list\": [1, 2]}}").unwrap();
+
+        assert_eq!(
+            Parser::parse("{\"USER\": {\"PASSWORD\": \"***\", \"LIST\": [null, null]}}"),
+            Ok(value.fold(&mut Redact))
+        );
+    }
+}
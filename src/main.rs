@@ -1,8 +1,37 @@
-mod json;
+use std::error::Error;
+use std::io::Read;
+use std::{env, fs, io};
 
-use json::parser::Parser;
+use rust_json::json::jq::Filter;
+use rust_json::json::parser::Parser;
+use rust_json::json::serializer::Serializer;
+
+/// Runs `filter` over the JSON in `file`, or standard input, printing one output per line.
+fn run_filter(filter: &str, file: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let filter = Filter::parse(filter)?;
+    let text = match file {
+        Some(file) => fs::read_to_string(file)?,
+        None => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        }
+    };
+
+    let value = Parser::parse(&text)?;
+    for output in filter.apply(&value)? {
+        println!("{}", Serializer::serialize(&output));
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(filter) = args.first() {
+        return run_filter(filter, args.get(1).map(String::as_str));
+    }
 
-fn main() {
     let json_data = "
 [
     { \"name\": \"Alice\", \"age\": 15 },
@@ -11,12 +40,13 @@ fn main() {
 ]
     ";
 
-    let obj = Parser::parse(json_data).unwrap();
-    for i in 0..3 {
-        let map = obj.get_as_array(i).unwrap();
-        let name = map.get_as_object("name").unwrap().as_string().unwrap();
-        let age = map.get_as_object("age").unwrap().as_i64().unwrap();
+    let obj = Parser::parse(json_data)?;
+    for (i, user) in obj.access().elements()?.enumerate() {
+        let name = user.str("name")?;
+        let age = user.i64("age")?;
 
         println!("{} name: {}, age: {}", i, name, age);
     }
+
+    Ok(())
 }